@@ -1,24 +1,20 @@
 #![feature(link_args)]
-#[macro_use(napi_module)]
+#[macro_use(napi_register_module_v1)]
 extern crate node_api;
 
-napi_module!("testmod", register);
-
-use node_api::{NapiEnv, NapiValue, FromNapiValues, IntoNapiValue};
-use node_api::{create_function, set_named_property, create_object};
-use node_api::error::*;
-
-#[no_mangle]
-pub extern "C" fn register(env: NapiEnv, exports: NapiValue, _module: NapiValue, _priv: *mut std::os::raw::c_void) {
-    let function = create_function(env, "foo", |_: NapiEnv, _: NapiValue, ()| {
+napi_register_module_v1!(|exports| {
+    exports.function("hello", |_: NapiEnv, _: NapiValue, ()| {
         HelloReturn {
             foo: "hello".to_string(),
             bar: 42,
         }
-    })
-            .expect("error creating function");
-    set_named_property(env, exports, "hello", function).expect("error attaching function");
-}
+    })?;
+    Ok(())
+});
+
+use node_api::{NapiEnv, NapiValue, FromNapiValues, IntoNapiValue};
+use node_api::{set_named_property, create_object};
+use node_api::error::*;
 
 struct HelloArgs {}
 impl FromNapiValues for HelloArgs {
@@ -45,15 +45,65 @@ pub struct napi_async_work__ {
     _unused: [u8; 0],
 }
 pub type napi_async_work = *mut napi_async_work__;
-#[repr(u32)]
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
-pub enum napi_property_attributes {
-    napi_default = 0,
-    napi_writable = 1,
-    napi_enumerable = 2,
-    napi_configurable = 4,
-    napi_static = 1024,
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct napi_deferred__ {
+    _unused: [u8; 0],
+}
+pub type napi_deferred = *mut napi_deferred__;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct napi_threadsafe_function__ {
+    _unused: [u8; 0],
+}
+pub type napi_threadsafe_function = *mut napi_threadsafe_function__;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct napi_async_cleanup_hook_handle__ {
+    _unused: [u8; 0],
+}
+pub type napi_async_cleanup_hook_handle = *mut napi_async_cleanup_hook_handle__;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct napi_async_context__ {
+    _unused: [u8; 0],
+}
+pub type napi_async_context = *mut napi_async_context__;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct napi_callback_scope__ {
+    _unused: [u8; 0],
+}
+pub type napi_callback_scope = *mut napi_callback_scope__;
+pub const napi_property_attributes_napi_default: napi_property_attributes =
+    napi_property_attributes(0);
+pub const napi_property_attributes_napi_writable: napi_property_attributes =
+    napi_property_attributes(1);
+pub const napi_property_attributes_napi_enumerable: napi_property_attributes =
+    napi_property_attributes(2);
+pub const napi_property_attributes_napi_configurable: napi_property_attributes =
+    napi_property_attributes(4);
+pub const napi_property_attributes_napi_static: napi_property_attributes =
+    napi_property_attributes(1024);
+impl ::std::ops::BitOr<napi_property_attributes> for napi_property_attributes {
+    type
+    Output
+    =
+    Self;
+    #[inline]
+    fn bitor(self, other: Self) -> Self {
+        napi_property_attributes(self.0 | other.0)
+    }
+}
+impl ::std::ops::BitOrAssign for napi_property_attributes {
+    #[inline]
+    fn bitor_assign(&mut self, rhs: napi_property_attributes) {
+        self.0 |= rhs.0;
+    }
 }
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct napi_property_attributes(pub u32);
 #[repr(u32)]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum napi_valuetype {
@@ -95,7 +145,55 @@ pub enum napi_status {
     napi_generic_failure = 9,
     napi_pending_exception = 10,
     napi_cancelled = 11,
-    napi_status_last = 12,
+    napi_escape_called_twice = 12,
+    napi_handle_scope_mismatch = 13,
+    napi_callback_scope_mismatch = 14,
+    napi_queue_full = 15,
+    napi_closing = 16,
+    napi_status_last = 17,
+}
+#[repr(u32)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum napi_key_collection_mode {
+    napi_key_include_prototypes = 0,
+    napi_key_own_only = 1,
+}
+pub const napi_key_filter_napi_key_all_properties: napi_key_filter = napi_key_filter(0);
+pub const napi_key_filter_napi_key_writable: napi_key_filter = napi_key_filter(1);
+pub const napi_key_filter_napi_key_enumerable: napi_key_filter = napi_key_filter(2);
+pub const napi_key_filter_napi_key_configurable: napi_key_filter = napi_key_filter(4);
+pub const napi_key_filter_napi_key_skip_strings: napi_key_filter = napi_key_filter(8);
+pub const napi_key_filter_napi_key_skip_symbols: napi_key_filter = napi_key_filter(16);
+impl ::std::ops::BitOr<napi_key_filter> for napi_key_filter {
+    type
+    Output
+    =
+    Self;
+    #[inline]
+    fn bitor(self, other: Self) -> Self {
+        napi_key_filter(self.0 | other.0)
+    }
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct napi_key_filter(pub u32);
+#[repr(u32)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum napi_key_conversion {
+    napi_key_keep_numbers = 0,
+    napi_key_numbers_to_strings = 1,
+}
+#[repr(u32)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum napi_threadsafe_function_release_mode {
+    napi_tsfn_release = 0,
+    napi_tsfn_abort = 1,
+}
+#[repr(u32)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum napi_threadsafe_function_call_mode {
+    napi_tsfn_nonblocking = 0,
+    napi_tsfn_blocking = 1,
 }
 pub type napi_callback =
     ::std::option::Option<unsafe extern "C" fn(env: napi_env,
@@ -107,10 +205,22 @@ pub type napi_finalize =
                                                    *mut ::std::os::raw::c_void,
                                                finalize_hint:
                                                    *mut ::std::os::raw::c_void)>;
+pub type napi_async_cleanup_hook =
+    ::std::option::Option<unsafe extern "C" fn(handle:
+                                                   napi_async_cleanup_hook_handle,
+                                               data:
+                                                   *mut ::std::os::raw::c_void)>;
 pub type napi_async_execute_callback =
     ::std::option::Option<unsafe extern "C" fn(env: napi_env,
                                                data:
                                                    *mut ::std::os::raw::c_void)>;
+pub type napi_threadsafe_function_call_js =
+    ::std::option::Option<unsafe extern "C" fn(env: napi_env,
+                                               js_callback: napi_value,
+                                               context:
+                                                   *mut ::std::os::raw::c_void,
+                                               data:
+                                                   *mut ::std::os::raw::c_void)>;
 pub type napi_async_complete_callback =
     ::std::option::Option<unsafe extern "C" fn(env: napi_env,
                                                status: napi_status,
@@ -229,10 +339,8 @@ impl Clone for napi_extended_error_info {
 }
 pub type napi_addon_register_func =
     ::std::option::Option<unsafe extern "C" fn(env: napi_env,
-                                               exports: napi_value,
-                                               module: napi_value,
-                                               priv_:
-                                                   *mut ::std::os::raw::c_void)>;
+                                               exports: napi_value)
+                              -> napi_value>;
 #[repr(C)]
 #[derive(Debug, Copy)]
 pub struct napi_module {
@@ -356,20 +464,22 @@ extern "C" {
 extern "C" {
     pub fn napi_create_function(env: napi_env,
                                 utf8name: *const ::std::os::raw::c_char,
-                                cb: napi_callback,
+                                length: usize, cb: napi_callback,
                                 data: *mut ::std::os::raw::c_void,
                                 result: *mut napi_value) -> napi_status;
 }
 extern "C" {
-    pub fn napi_create_error(env: napi_env, msg: napi_value,
+    pub fn napi_create_error(env: napi_env, code: napi_value, msg: napi_value,
                              result: *mut napi_value) -> napi_status;
 }
 extern "C" {
-    pub fn napi_create_type_error(env: napi_env, msg: napi_value,
+    pub fn napi_create_type_error(env: napi_env, code: napi_value,
+                                  msg: napi_value,
                                   result: *mut napi_value) -> napi_status;
 }
 extern "C" {
-    pub fn napi_create_range_error(env: napi_env, msg: napi_value,
+    pub fn napi_create_range_error(env: napi_env, code: napi_value,
+                                   msg: napi_value,
                                    result: *mut napi_value) -> napi_status;
 }
 extern "C" {
@@ -437,6 +547,13 @@ extern "C" {
     pub fn napi_get_property_names(env: napi_env, object: napi_value,
                                    result: *mut napi_value) -> napi_status;
 }
+extern "C" {
+    pub fn napi_get_all_property_names(env: napi_env, object: napi_value,
+                                       key_mode: napi_key_collection_mode,
+                                       key_filter: napi_key_filter,
+                                       key_conversion: napi_key_conversion,
+                                       result: *mut napi_value) -> napi_status;
+}
 extern "C" {
     pub fn napi_set_property(env: napi_env, object: napi_value,
                              key: napi_value, value: napi_value)
@@ -452,6 +569,11 @@ extern "C" {
                              key: napi_value, result: *mut napi_value)
      -> napi_status;
 }
+extern "C" {
+    pub fn napi_delete_property(env: napi_env, object: napi_value,
+                                key: napi_value, result: *mut bool)
+     -> napi_status;
+}
 extern "C" {
     pub fn napi_set_named_property(env: napi_env, object: napi_value,
                                    utf8name: *const ::std::os::raw::c_char,
@@ -514,11 +636,30 @@ extern "C" {
      -> napi_status;
 }
 extern "C" {
-    pub fn napi_make_callback(env: napi_env, recv: napi_value,
-                              func: napi_value, argc: usize,
+    pub fn napi_make_callback(env: napi_env, async_context: napi_async_context,
+                              recv: napi_value, func: napi_value, argc: usize,
                               argv: *const napi_value,
                               result: *mut napi_value) -> napi_status;
 }
+extern "C" {
+    pub fn napi_async_init(env: napi_env, async_resource: napi_value,
+                           async_resource_name: napi_value,
+                           result: *mut napi_async_context) -> napi_status;
+}
+extern "C" {
+    pub fn napi_async_destroy(env: napi_env, async_context: napi_async_context)
+     -> napi_status;
+}
+extern "C" {
+    pub fn napi_open_callback_scope(env: napi_env, resource_object: napi_value,
+                                    context: napi_async_context,
+                                    result: *mut napi_callback_scope)
+     -> napi_status;
+}
+extern "C" {
+    pub fn napi_close_callback_scope(env: napi_env, scope: napi_callback_scope)
+     -> napi_status;
+}
 extern "C" {
     pub fn napi_get_cb_info(env: napi_env, cbinfo: napi_callback_info,
                             argc: *mut usize, argv: *mut napi_value,
@@ -527,13 +668,13 @@ extern "C" {
      -> napi_status;
 }
 extern "C" {
-    pub fn napi_is_construct_call(env: napi_env, cbinfo: napi_callback_info,
-                                  result: *mut bool) -> napi_status;
+    pub fn napi_get_new_target(env: napi_env, cbinfo: napi_callback_info,
+                               result: *mut napi_value) -> napi_status;
 }
 extern "C" {
     pub fn napi_define_class(env: napi_env,
                              utf8name: *const ::std::os::raw::c_char,
-                             constructor: napi_callback,
+                             length: usize, constructor: napi_callback,
                              data: *mut ::std::os::raw::c_void,
                              property_count: usize,
                              properties: *const napi_property_descriptor,
@@ -641,6 +782,9 @@ extern "C" {
                                              result: *mut napi_value)
      -> napi_status;
 }
+extern "C" {
+    pub fn napi_fatal_exception(env: napi_env, err: napi_value) -> napi_status;
+}
 extern "C" {
     pub fn napi_create_buffer(env: napi_env, length: usize,
                               data: *mut *mut ::std::os::raw::c_void,
@@ -716,6 +860,8 @@ extern "C" {
 }
 extern "C" {
     pub fn napi_create_async_work(env: napi_env,
+                                  async_resource: napi_value,
+                                  async_resource_name: napi_value,
                                   execute: napi_async_execute_callback,
                                   complete: napi_async_complete_callback,
                                   data: *mut ::std::os::raw::c_void,
@@ -734,6 +880,138 @@ extern "C" {
     pub fn napi_cancel_async_work(env: napi_env, work: napi_async_work)
      -> napi_status;
 }
+extern "C" {
+    pub fn napi_add_finalizer(env: napi_env, js_object: napi_value,
+                              finalize_data: *mut ::std::os::raw::c_void,
+                              finalize_cb: napi_finalize,
+                              finalize_hint: *mut ::std::os::raw::c_void,
+                              result: *mut napi_ref) -> napi_status;
+}
+extern "C" {
+    pub fn napi_create_promise(env: napi_env, deferred: *mut napi_deferred,
+                               promise: *mut napi_value) -> napi_status;
+}
+extern "C" {
+    pub fn napi_resolve_deferred(env: napi_env, deferred: napi_deferred,
+                                 resolution: napi_value) -> napi_status;
+}
+extern "C" {
+    pub fn napi_reject_deferred(env: napi_env, deferred: napi_deferred,
+                                rejection: napi_value) -> napi_status;
+}
+extern "C" {
+    pub fn napi_is_promise(env: napi_env, value: napi_value,
+                           is_promise: *mut bool) -> napi_status;
+}
+extern "C" {
+    pub fn napi_create_date(env: napi_env, time: f64, result: *mut napi_value)
+     -> napi_status;
+}
+extern "C" {
+    pub fn napi_is_date(env: napi_env, value: napi_value, is_date: *mut bool)
+     -> napi_status;
+}
+extern "C" {
+    pub fn napi_get_date_value(env: napi_env, value: napi_value,
+                               result: *mut f64) -> napi_status;
+}
+extern "C" {
+    pub fn napi_create_threadsafe_function(env: napi_env, func: napi_value,
+                                           async_resource: napi_value,
+                                           async_resource_name: napi_value,
+                                           max_queue_size: usize,
+                                           initial_thread_count: usize,
+                                           thread_finalize_data:
+                                               *mut ::std::os::raw::c_void,
+                                           thread_finalize_cb: napi_finalize,
+                                           context:
+                                               *mut ::std::os::raw::c_void,
+                                           call_js_cb:
+                                               napi_threadsafe_function_call_js,
+                                           result:
+                                               *mut napi_threadsafe_function)
+     -> napi_status;
+}
+extern "C" {
+    pub fn napi_get_threadsafe_function_context(func:
+                                                    napi_threadsafe_function,
+                                                result:
+                                                    *mut *mut ::std::os::raw::c_void)
+     -> napi_status;
+}
+extern "C" {
+    pub fn napi_call_threadsafe_function(func: napi_threadsafe_function,
+                                         data: *mut ::std::os::raw::c_void,
+                                         is_blocking:
+                                             napi_threadsafe_function_call_mode)
+     -> napi_status;
+}
+extern "C" {
+    pub fn napi_acquire_threadsafe_function(func: napi_threadsafe_function)
+     -> napi_status;
+}
+extern "C" {
+    pub fn napi_release_threadsafe_function(func: napi_threadsafe_function,
+                                            mode:
+                                                napi_threadsafe_function_release_mode)
+     -> napi_status;
+}
+extern "C" {
+    pub fn napi_unref_threadsafe_function(env: napi_env,
+                                          func: napi_threadsafe_function)
+     -> napi_status;
+}
+extern "C" {
+    pub fn napi_ref_threadsafe_function(env: napi_env,
+                                        func: napi_threadsafe_function)
+     -> napi_status;
+}
+extern "C" {
+    pub fn napi_set_instance_data(env: napi_env,
+                                  data: *mut ::std::os::raw::c_void,
+                                  finalize_cb: napi_finalize,
+                                  finalize_hint: *mut ::std::os::raw::c_void)
+     -> napi_status;
+}
+extern "C" {
+    pub fn napi_get_instance_data(env: napi_env,
+                                  data: *mut *mut ::std::os::raw::c_void)
+     -> napi_status;
+}
+extern "C" {
+    pub fn napi_add_env_cleanup_hook(env: napi_env,
+                                     fun:
+                                         ::std::option::Option<unsafe extern "C" fn(arg:
+                                                                                        *mut ::std::os::raw::c_void)>,
+                                     arg: *mut ::std::os::raw::c_void)
+     -> napi_status;
+}
+extern "C" {
+    pub fn napi_remove_env_cleanup_hook(env: napi_env,
+                                        fun:
+                                            ::std::option::Option<unsafe extern "C" fn(arg:
+                                                                                           *mut ::std::os::raw::c_void)>,
+                                        arg: *mut ::std::os::raw::c_void)
+     -> napi_status;
+}
+extern "C" {
+    pub fn napi_add_async_cleanup_hook(env: napi_env,
+                                       hook: napi_async_cleanup_hook,
+                                       arg: *mut ::std::os::raw::c_void,
+                                       remove_handle:
+                                           *mut napi_async_cleanup_hook_handle)
+     -> napi_status;
+}
+extern "C" {
+    pub fn napi_remove_async_cleanup_hook(remove_handle:
+                                              napi_async_cleanup_hook_handle)
+     -> napi_status;
+}
+extern "C" {
+    pub fn napi_remove_wrap(env: napi_env, js_object: napi_value,
+                            result: *mut *mut ::std::os::raw::c_void)
+     -> napi_status;
+}
 #[repr(C)]
 #[derive(Debug, Copy)]
 pub struct __va_list_tag {
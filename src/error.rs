@@ -1,4 +1,4 @@
-use std::{result, ffi, string};
+use std::{result, ffi, string, fmt, error};
 use std::ffi::CStr;
 
 use node_api_sys::*;
@@ -19,6 +19,14 @@ pub struct NapiError {
     pub error_code: NapiErrorType,
 }
 
+impl fmt::Display for NapiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.error_message)
+    }
+}
+
+impl error::Error for NapiError {}
+
 impl From<napi_extended_error_info> for NapiError {
     fn from(error: napi_extended_error_info) -> Self {
         unsafe {
@@ -33,6 +41,18 @@ impl From<napi_extended_error_info> for NapiError {
     }
 }
 
+// used where no env is available to fetch the extended error info, e.g. on
+// threads other than the main JS thread
+impl From<napi_status> for NapiError {
+    fn from(status: napi_status) -> Self {
+        NapiError {
+            error_message: format!("{:?}", status),
+            engine_error_code: 0,
+            error_code: NapiErrorType::from(status),
+        }
+    }
+}
+
 impl From<ffi::NulError> for NapiError {
     fn from(_: ffi::NulError) -> Self {
         make_generic_napi_error("string must not contain 0 byte")
@@ -58,6 +78,11 @@ pub enum NapiErrorType {
     GenericFailure,
     PendingException,
     Cancelled,
+    EscapeCalledTwice,
+    HandleScopeMismatch,
+    CallbackScopeMismatch,
+    QueueFull,
+    Closing,
     StatusLast,
 }
 
@@ -75,6 +100,11 @@ impl From<napi_status> for NapiErrorType {
             napi_status::napi_generic_failure => NapiErrorType::GenericFailure,
             napi_status::napi_pending_exception => NapiErrorType::PendingException,
             napi_status::napi_cancelled => NapiErrorType::Cancelled,
+            napi_status::napi_escape_called_twice => NapiErrorType::EscapeCalledTwice,
+            napi_status::napi_handle_scope_mismatch => NapiErrorType::HandleScopeMismatch,
+            napi_status::napi_callback_scope_mismatch => NapiErrorType::CallbackScopeMismatch,
+            napi_status::napi_queue_full => NapiErrorType::QueueFull,
+            napi_status::napi_closing => NapiErrorType::Closing,
             napi_status::napi_status_last => NapiErrorType::StatusLast,
             _ => NapiErrorType::GenericFailure,
         }
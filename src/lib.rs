@@ -2,40 +2,152 @@
 
 extern crate node_api_sys;
 extern crate futures;
+#[cfg(feature = "compat")]
+extern crate futures01;
+#[cfg(feature = "tokio")]
+extern crate tokio;
+#[cfg(feature = "chrono")]
+extern crate chrono;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
+#[cfg(feature = "serde_json")]
+extern crate serde_json;
 
 mod napi;
 mod napi_value;
+mod napi_call_context;
+mod napi_js_value;
+mod napi_js_types;
+mod napi_date;
+#[cfg(feature = "serde")]
+mod napi_serde;
+#[cfg(feature = "serde_json")]
+mod napi_json;
+mod napi_env;
+mod napi_reference;
+mod napi_async_context;
 mod napi_futures;
+mod napi_promise;
+mod napi_async_work;
+mod napi_threadsafe_function;
+mod napi_callback;
+mod napi_abort;
+mod napi_property;
+mod napi_class;
+mod napi_exports;
 pub mod error;
 
 pub use napi::*;
-pub use napi_value::{FromNapiValues, IntoNapiValue};
+pub use napi_value::{FromNapiValue, FromNapiValues, IntoNapiValue, IntoNapiValues, Coercible,
+                     Coerced, Rest, IntoReturnValue, ReturnsValue, ReturnsFuture};
+pub use napi_call_context::{CallContext, Arguments};
+#[cfg(feature = "serde")]
+pub use napi_serde::{Serde, to_js, from_js};
+pub use napi_js_value::JsValue;
+pub use napi_js_types::{JsType, JsUndefined, JsNull, JsBoolean, JsNumber, JsString, JsSymbol,
+                        JsFunction, JsExternal, JsObject, JsArray, JsBuffer, JsTypedArray,
+                        JsPromise, JsError, JsDate};
+pub use napi_env::{NapiEnvExt, CleanupHook, AsyncCleanupHook, AsyncCleanup};
+pub use napi_reference::Reference;
+pub use napi_async_context::{AsyncContext, CallbackScope};
+pub use napi_promise::{Deferred, ThreadsafeDeferred, Promise};
+pub use napi_futures::{Executor, Async, spawn_promise};
+pub use napi_async_work::{AsyncTask, AsyncWork, Task, AbortableTask, queue_async_task,
+                          queue_abortable_task};
+pub use napi_threadsafe_function::{ThreadsafeFunction, CallMode};
+pub use napi_callback::{JsCallback, PersistentCallback};
+pub use napi_property::{PropertyDescriptor, define_properties};
+pub use napi_class::{Class, define_class, with_instance};
+pub use napi_exports::{Exports, init_module};
+pub use napi_abort::{CancellationToken, AbortSignal, AbortError, AbortableError, Abortable,
+                    abortable};
 
+/// Registers a native module. Either takes the name of an `extern "C"`
+/// register function, or an init closure receiving the module's `Exports`:
+///
+/// ```ignore
+/// napi_module!("hello", |exports| {
+///     exports.function("hello", |_, _, ()| "world")?;
+///     Ok(())
+/// });
+/// ```
+///
+/// Errors returned from the closure are thrown to the caller of `require()`.
+/// The macro can be invoked more than once per crate. As node only loads one
+/// module per binary, the exports of each module then end up in an object of
+/// their own, exported under the module's name.
 #[macro_export]
 macro_rules! napi_module {
     ($module:expr, $register_func:ident) => {
-const NAPI_MODULE_VERSION: std::os::raw::c_int = 1;
-
-#[cfg_attr(target_os = "macos", link_args = "-Wl,-undefined,dynamic_lookup")]
-extern "C" {}
-
-#[cfg_attr(target_os = "linux", link_section = ".ctors")]
-#[cfg_attr(target_os = "macos", link_section = "__DATA,__mod_init_func")]
-#[cfg_attr(target_os = "windows", link_section = ".CRT$XCU")]
-pub static REGISTER_FOO: extern "C" fn() = {
-    extern "C" fn __load_napi_module() {
-        node_api::module_register(node_api::NapiModule {
-                            version: NAPI_MODULE_VERSION,
-                            flags: 0,
-                            filename: $module.to_string(),
-                            register_func: Some($register_func),
-                            modname: $module.to_string(),
-                        })
-                .expect("error registering module");
+const _: () = {
+    const NAPI_MODULE_VERSION: std::os::raw::c_int = 1;
+
+    #[cfg_attr(target_os = "macos", link_args = "-Wl,-undefined,dynamic_lookup")]
+    extern "C" {}
+
+    #[used]
+    #[cfg_attr(any(target_os = "linux", target_os = "android", target_os = "freebsd"),
+               link_section = ".init_array")]
+    #[cfg_attr(target_os = "macos", link_section = "__DATA,__mod_init_func")]
+    #[cfg_attr(target_os = "windows", link_section = ".CRT$XCU")]
+    static REGISTER_MODULE: extern "C" fn() = {
+        extern "C" fn __load_napi_module() {
+            node_api::module_register(node_api::NapiModule {
+                                version: NAPI_MODULE_VERSION,
+                                flags: 0,
+                                filename: $module.to_string(),
+                                register_func: Some($register_func),
+                                modname: $module.to_string(),
+                            })
+                    .expect("error registering module");
+        }
+        __load_napi_module
+    };
+};
+};
+    ($module:expr, $init:expr) => {
+const _: () = {
+    extern "C" fn __napi_init(env: node_api::NapiEnv,
+                              exports: node_api::NapiValue,
+                              module: node_api::NapiValue,
+                              _priv: *mut std::os::raw::c_void) {
+        node_api::init_module(env, exports, module, $init)
+    }
+
+    napi_module!($module, __napi_init);
+};
+};
+}
+
+/// Exports the `napi_register_module_v1` symbol that newer engines look up
+/// when loading a module, instead of relying on registration from a static
+/// constructor. Takes the same arguments as `napi_module!` minus the name, and
+/// `Exports::module` is `undefined` for modules loaded this way. Only one
+/// module per binary can be exported like this.
+#[macro_export]
+macro_rules! napi_register_module_v1 {
+    ($register_func:ident) => {
+#[no_mangle]
+pub extern "C" fn napi_register_module_v1(env: node_api::NapiEnv,
+                                          exports: node_api::NapiValue)
+                                          -> node_api::NapiValue {
+    node_api::register_module_v1(env, exports, Some($register_func))
+}
+};
+    ($init:expr) => {
+const _: () = {
+    extern "C" fn __napi_init(env: node_api::NapiEnv,
+                              exports: node_api::NapiValue,
+                              module: node_api::NapiValue,
+                              _priv: *mut std::os::raw::c_void) {
+        node_api::init_module(env, exports, module, $init)
     }
-    __load_napi_module
+
+    napi_register_module_v1!(__napi_init);
 };
-}}
+};
+}
 
 #[cfg(test)]
 mod tests {
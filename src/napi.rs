@@ -1,24 +1,44 @@
 use std::ffi::CString;
 use std::{ptr,mem,f64,usize,os};
 use std::result::Result as StdResult;
+use std::any::Any;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::{Mutex, Once};
 use node_api_sys::*;
 
-use napi_value::{FromNapiValues, IntoNapiValue};
+use std::future::Future;
+
+use napi_value::{FromNapiValues, IntoNapiValue, IntoReturnValue};
+use napi_js_value::JsValue;
+use napi_call_context::CallContext;
+use napi_futures::Async;
 use error::*;
 
 pub type NapiEnv = napi_env;
 pub type NapiRef = napi_ref;
 pub type NapiValue = napi_value;
+pub type NapiDeferred = napi_deferred;
+pub type NapiAsyncWork = napi_async_work;
+pub type NapiAsyncContext = napi_async_context;
+pub type NapiCallbackScope = napi_callback_scope;
 
 #[derive(Debug, Clone)]
 pub struct NapiModule {
     pub version: i32,
     pub flags: u32,
     pub filename: String,
-    pub register_func: napi_addon_register_func,
+    pub register_func: NapiRegisterFunc,
     pub modname: String,
 }
 
+// the register functions generated by `napi_module!`. Engines no longer pass
+// a `module` object to modules, so `module` is `undefined`.
+pub type NapiRegisterFunc = Option<unsafe extern "C" fn(env: NapiEnv,
+                                                        exports: NapiValue,
+                                                        module: NapiValue,
+                                                        priv_: *mut os::raw::c_void)>;
+
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum NapiValueType {
@@ -49,6 +69,47 @@ impl From<napi_valuetype> for NapiValueType {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TypedArrayType {
+    Int8,
+    Uint8,
+    Uint8Clamped,
+    Int16,
+    Uint16,
+    Int32,
+    Uint32,
+    Float32,
+    Float64,
+}
+
+impl From<napi_typedarray_type> for TypedArrayType {
+    fn from(s: napi_typedarray_type) -> Self {
+        match s {
+            napi_typedarray_type::napi_int8_array => TypedArrayType::Int8,
+            napi_typedarray_type::napi_uint8_array => TypedArrayType::Uint8,
+            napi_typedarray_type::napi_uint8_clamped_array => TypedArrayType::Uint8Clamped,
+            napi_typedarray_type::napi_int16_array => TypedArrayType::Int16,
+            napi_typedarray_type::napi_uint16_array => TypedArrayType::Uint16,
+            napi_typedarray_type::napi_int32_array => TypedArrayType::Int32,
+            napi_typedarray_type::napi_uint32_array => TypedArrayType::Uint32,
+            napi_typedarray_type::napi_float32_array => TypedArrayType::Float32,
+            napi_typedarray_type::napi_float64_array => TypedArrayType::Float64,
+        }
+    }
+}
+
+impl TypedArrayType {
+    // the size of each element in bytes
+    pub fn element_size(&self) -> usize {
+        match *self {
+            TypedArrayType::Int8 | TypedArrayType::Uint8 | TypedArrayType::Uint8Clamped => 1,
+            TypedArrayType::Int16 | TypedArrayType::Uint16 => 2,
+            TypedArrayType::Int32 | TypedArrayType::Uint32 | TypedArrayType::Float32 => 4,
+            TypedArrayType::Float64 => 8,
+        }
+    }
+}
+
 pub fn napi_either<T>(env: NapiEnv, status: napi_status, val: T) -> Result<T> {
     match status {
         napi_status::napi_ok => Ok(val),
@@ -74,25 +135,80 @@ fn get_last_napi_error(env: NapiEnv) -> StdResult<NapiError, NapiErrorType> {
         .map_err(|err| NapiErrorType::from(err))
 }
 
+// modules registered so far. The engine only loads one module per binary, so
+// all of them are registered as one that loads each of them.
+static MODULES: Mutex<Vec<NapiModule>> = Mutex::new(Vec::new());
+static REGISTER_MODULES: Once = Once::new();
+
+// the engine keeps using the module after `napi_module_register` returns, so
+// the module and its names are leaked to give them a 'static lifetime
 pub fn module_register(mod_: NapiModule) -> StdResult<(), NapiError> {
-    let module = &mut napi_module {
-                          nm_version: mod_.version,
-                          nm_flags: mod_.flags,
-                          nm_filename: CString::new(mod_.filename)?.as_ptr(),
-                          nm_register_func: mod_.register_func,
-                          nm_modname: try!(CString::new(mod_.modname)).as_ptr(),
-                          nm_priv: ptr::null_mut(),
-                          reserved: [ptr::null_mut(),
-                                     ptr::null_mut(),
-                                     ptr::null_mut(),
-                                     ptr::null_mut()],
-                      };
-    unsafe {
-        napi_module_register(module);
-    }
+    let filename = CString::new(mod_.filename.as_str())?;
+    let modname = CString::new(mod_.modname.as_str())?;
+    let version = mod_.version;
+    let flags = mod_.flags;
+    MODULES.lock().unwrap_or_else(|err| err.into_inner()).push(mod_);
+    REGISTER_MODULES.call_once(|| {
+        let module: &'static mut napi_module = Box::leak(Box::new(napi_module {
+                                                                      nm_version: version,
+                                                                      nm_flags: flags,
+                                                                      nm_filename: filename.into_raw(),
+                                                                      nm_register_func: Some(register_modules),
+                                                                      nm_modname: modname.into_raw(),
+                                                                      nm_priv: ptr::null_mut(),
+                                                                      reserved: [ptr::null_mut(),
+                                                                                 ptr::null_mut(),
+                                                                                 ptr::null_mut(),
+                                                                                 ptr::null_mut()],
+                                                                  }));
+        unsafe {
+            napi_module_register(module);
+        }
+    });
     Ok(())
 }
 
+// a single module fills in the exports itself. When there are several, each
+// one gets an exports object of its own, exported under the module's name.
+unsafe extern "C" fn register_modules(env: NapiEnv, exports: NapiValue) -> NapiValue {
+    let modules = MODULES.lock().unwrap_or_else(|err| err.into_inner()).clone();
+    if let [ref single] = modules[..] {
+        return register_module_v1(env, exports, single.register_func);
+    }
+    for mod_ in modules {
+        let result = create_object(env).and_then(|module_exports| {
+            register_module_v1(env, module_exports, mod_.register_func);
+            if is_exception_pending(env)? {
+                return Ok(false);
+            }
+            set_named_property(env, exports, &mod_.modname, module_exports)?;
+            Ok(true)
+        });
+        match result {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(err) => {
+                let _ = create_error(env, &err.error_message).and_then(|error| throw(env, error));
+                break;
+            }
+        }
+    }
+    exports
+}
+
+// calls a register function generated by `napi_module!` the way current
+// engines load modules, e.g. through the exported `napi_register_module_v1`
+// symbol, without a `module` object
+pub fn register_module_v1(env: NapiEnv,
+                          exports: NapiValue,
+                          register_func: NapiRegisterFunc)
+                          -> NapiValue {
+    let register_func = register_func.expect("no register function given");
+    let module = get_undefined(env).unwrap_or(0);
+    unsafe { register_func(env, exports, module, ptr::null_mut()) };
+    exports
+}
+
 pub fn get_undefined(env: NapiEnv) -> Result<NapiValue> {
     unsafe {
         let mut napi_val: NapiValue = mem::uninitialized();
@@ -171,67 +287,210 @@ pub fn create_string_utf8<T>(env: NapiEnv, val: T) -> Result<NapiValue>
     napi_either(env, status, napi_val)
 }
 
+// passes the length along, so that unlike `create_string_utf8` the string
+// may contain 0 bytes
+pub fn create_string_utf8_with_len(env: NapiEnv, val: &str) -> Result<NapiValue> {
+    let mut napi_val: NapiValue = 0;
+    let status = unsafe {
+        napi_create_string_utf8(env,
+                                val.as_ptr() as *const os::raw::c_char,
+                                val.len(),
+                                &mut napi_val)
+    };
+    napi_either(env, status, napi_val)
+}
+
 //     pub fn napi_create_symbol(env: napi_env, description: napi_value,
 //                               result: *mut napi_value) -> napi_status;
+pub fn create_symbol(env: NapiEnv, description: Option<&str>) -> Result<NapiValue> {
+    let description = match description {
+        Some(description) => create_string_utf8(env, description)?,
+        None => 0,
+    };
+    let mut result: NapiValue = 0;
+    let status = unsafe { napi_create_symbol(env, description, &mut result) };
+    napi_either(env, status, result)
+}
+
+// the symbols JS uses for its protocols, e.g. `Symbol.iterator`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WellKnownSymbol {
+    AsyncIterator,
+    HasInstance,
+    Iterator,
+    ToPrimitive,
+    ToStringTag,
+}
+
+impl WellKnownSymbol {
+    fn name(self) -> &'static str {
+        match self {
+            WellKnownSymbol::AsyncIterator => "asyncIterator",
+            WellKnownSymbol::HasInstance => "hasInstance",
+            WellKnownSymbol::Iterator => "iterator",
+            WellKnownSymbol::ToPrimitive => "toPrimitive",
+            WellKnownSymbol::ToStringTag => "toStringTag",
+        }
+    }
+}
+
+// looks the symbol up on the global `Symbol`, as N-API has no direct access
+pub fn get_well_known_symbol(env: NapiEnv, symbol: WellKnownSymbol) -> Result<NapiValue> {
+    let global = get_global(env)?;
+    let symbol_constructor = get_named_property(env, global, "Symbol")?;
+    get_named_property(env, symbol_constructor, symbol.name())
+}
+
+// returns `None` for symbols created without a description
+pub fn get_symbol_description(env: NapiEnv, symbol: NapiValue) -> Result<Option<String>> {
+    let description = get_named_property(env, symbol, "description")?;
+    match type_of(env, description)? {
+        NapiValueType::Undefined => Ok(None),
+        _ => get_value_string_utf8(env, description).map(Some),
+    }
+}
 
-pub fn create_function<F, T, R>(env: NapiEnv, utf8name: &str, f: F) -> Result<NapiValue>
-    where F: Fn(NapiEnv, NapiValue, T) -> R,
+// `f` is owned by the function and dropped once it has been garbage collected
+pub fn create_function<F, T, R, M>(env: NapiEnv, utf8name: &str, f: F) -> Result<NapiValue>
+    where F: Fn(NapiEnv, NapiValue, T) -> R + 'static,
+          T: FromNapiValues,
+          R: IntoReturnValue<M>
+{
+    create_function_callback(env, utf8name, None, f)
+}
+
+// attaches `data` to the function, which the callback can get back from a
+// `CallContext` argument with `function_data`
+pub fn create_function_with_data<D, F, T, R, M>(env: NapiEnv,
+                                                utf8name: &str,
+                                                data: D,
+                                                f: F)
+                                                -> Result<NapiValue>
+    where D: Any,
+          F: Fn(NapiEnv, NapiValue, T) -> R + 'static,
+          T: FromNapiValues,
+          R: IntoReturnValue<M>
+{
+    create_function_callback(env, utf8name, Some(Rc::new(data)), f)
+}
+
+// a function that calls back into itself while `f` is running throws instead
+// of calling `f` again
+pub fn create_function_mut<F, T, R, M>(env: NapiEnv, utf8name: &str, f: F) -> Result<NapiValue>
+    where F: FnMut(NapiEnv, NapiValue, T) -> R + 'static,
+          T: FromNapiValues,
+          R: IntoReturnValue<M>
+{
+    let name = utf8name.to_string();
+    let f = RefCell::new(f);
+    create_function(env, utf8name, move |env, this, args| -> Result<JsValue> {
+        let mut f = f.try_borrow_mut()
+            .map_err(|_| {
+                         NapiError {
+                             error_message: format!("function {} called reentrantly", name),
+                             engine_error_code: 0,
+                             error_code: NapiErrorType::GenericFailure,
+                         }
+                     })?;
+        let value = (&mut *f)(env, this, args).into_return_value(env)?;
+        Ok(JsValue::new(env, value))
+    })
+}
+
+// the data of functions, with `data` first so that it can be found without
+// knowing `F`
+#[repr(C)]
+struct FunctionCallback<F> {
+    data: Option<Rc<Any>>,
+    f: F,
+}
+
+//     pub fn napi_create_function(env: napi_env,
+//                                 utf8name: *const ::std::os::raw::c_char,
+//                                 length: usize, cb: napi_callback,
+//                                 data: *mut ::std::os::raw::c_void,
+//                                 result: *mut napi_value) -> napi_status;
+fn create_function_callback<F, T, R, M>(env: NapiEnv,
+                                        utf8name: &str,
+                                        data: Option<Rc<Any>>,
+                                        f: F)
+                                        -> Result<NapiValue>
+    where F: Fn(NapiEnv, NapiValue, T) -> R + 'static,
           T: FromNapiValues,
-          R: IntoNapiValue
+          R: IntoReturnValue<M>
 {
-    unsafe extern "C" fn wrapper<F, T, R>(env: NapiEnv, cbinfo: napi_callback_info) -> NapiValue
-        where F: Fn(NapiEnv, NapiValue, T) -> R,
+    unsafe extern "C" fn wrapper<F, T, R, M>(env: NapiEnv, cbinfo: napi_callback_info) -> NapiValue
+        where F: Fn(NapiEnv, NapiValue, T) -> R + 'static,
               T: FromNapiValues,
-              R: IntoNapiValue
+              R: IntoReturnValue<M>
     {
-        let mut argc: usize = 16;
-        let mut argv: [NapiValue; 16] = mem::uninitialized();
-        let mut user_data = ptr::null_mut();
-        let mut this: NapiValue = 0;
-        let status = napi_get_cb_info(env,
-                                      cbinfo,
-                                      &mut argc,
-                                      argv.as_mut_ptr(),
-                                      &mut this,
-                                      &mut user_data);
-        assert!(status == napi_status::napi_ok);
-        assert!(user_data != ptr::null_mut());
-
-        let args =
-            T::from_napi_values(env, this, &argv[0..argc]).expect("cannot convert arguments");
-
-        let callback: Box<Option<F>> = Box::from_raw(user_data as *mut Option<F>);
-
-        let return_value = callback.expect("no callback found")(env, this, args);
-        return_value
-            .into_napi_value(env)
-            .unwrap_or(get_undefined(env).unwrap())
+        // failed conversions and returned errors are thrown
+        let result = CallContext::new(env, cbinfo).and_then(|context| {
+            let user_data = context.data();
+            assert!(user_data != ptr::null_mut());
+            let callback = &*(user_data as *const FunctionCallback<F>);
+            let context = context.with_function_data(callback.data.clone());
+
+            let args = T::from_call_context(&context)?;
+
+            (callback.f)(env, context.raw_this(), args).into_return_value(env)
+        });
+        match result {
+            Ok(value) => value,
+            Err(err) => {
+                let _ = err.into_napi_value(env).and_then(|error| throw(env, error));
+                0
+            }
+        }
     }
 
-    let boxed_f = Box::new(Some(f));
-    let user_data = Box::into_raw(boxed_f) as *mut os::raw::c_void;
+    let callback = Box::new(FunctionCallback { data: data, f: f });
+    let user_data = &*callback as *const FunctionCallback<F> as *mut os::raw::c_void;
     let mut napi_val: NapiValue = 0;
-    let name = CString::new(utf8name)?;
     let status = unsafe {
         napi_create_function(env,
-                             name.into_raw(),
-                             Some(wrapper::<F, T, R>),
+                             utf8name.as_ptr() as *const os::raw::c_char,
+                             utf8name.len(),
+                             Some(wrapper::<F, T, R, M>),
                              user_data,
                              &mut napi_val)
     };
-    napi_either(env, status, napi_val)
+    napi_either(env, status, ())?;
+    add_finalizer(env, napi_val, callback)?;
+    Ok(napi_val)
 }
 
 
-//     pub fn napi_create_error(env: napi_env, msg: napi_value,
+// creates a function returning a promise that settles with the output of the
+// future returned by `f`, which is polled on the env's executor. The future
+// runs off the main thread and must not call into N-API itself.
+pub fn create_async_function<F, T, Fut, R, E>(env: NapiEnv, utf8name: &str, f: F) -> Result<NapiValue>
+    where F: Fn(NapiEnv, NapiValue, T) -> Fut + 'static,
+          T: FromNapiValues,
+          Fut: Future<Output = StdResult<R, E>> + Send + 'static,
+          R: IntoNapiValue + Send + 'static,
+          E: IntoNapiValue + Send + 'static
+{
+    create_function(env, utf8name, move |env, this, args| Async(f(env, this, args)))
+}
+
+//     pub fn napi_create_error(env: napi_env, code: napi_value, msg: napi_value,
 //                              result: *mut napi_value) -> napi_status;
+pub fn create_error(env: NapiEnv, message: &str) -> Result<NapiValue> {
+    let msg = create_string_utf8(env, message)?;
+    let mut result: NapiValue = 0;
+    let status = unsafe { napi_create_error(env, 0, msg, &mut result) };
+    napi_either(env, status, result)
+}
 
 
-//     pub fn napi_create_type_error(env: napi_env, msg: napi_value,
+//     pub fn napi_create_type_error(env: napi_env, code: napi_value,
+//                                   msg: napi_value,
 //                                   result: *mut napi_value) -> napi_status;
 
 
-//     pub fn napi_create_range_error(env: napi_env, msg: napi_value,
+//     pub fn napi_create_range_error(env: napi_env, code: napi_value,
+//                                    msg: napi_value,
 //                                    result: *mut napi_value) -> napi_status;
 
 
@@ -320,41 +579,116 @@ pub fn get_value_string_utf8(env: NapiEnv, value: NapiValue) -> Result<String> {
 
 //     pub fn napi_coerce_to_bool(env: napi_env, value: napi_value,
 //                                result: *mut napi_value) -> napi_status;
+pub fn coerce_to_bool(env: NapiEnv, value: NapiValue) -> Result<NapiValue> {
+    let mut result: NapiValue = 0;
+    let status = unsafe { napi_coerce_to_bool(env, value, &mut result) };
+    napi_either(env, status, result)
+}
 
 
 //     pub fn napi_coerce_to_number(env: napi_env, value: napi_value,
 //                                  result: *mut napi_value) -> napi_status;
+pub fn coerce_to_number(env: NapiEnv, value: NapiValue) -> Result<NapiValue> {
+    let mut result: NapiValue = 0;
+    let status = unsafe { napi_coerce_to_number(env, value, &mut result) };
+    napi_either(env, status, result)
+}
 
 
 //     pub fn napi_coerce_to_object(env: napi_env, value: napi_value,
 //                                  result: *mut napi_value) -> napi_status;
+pub fn coerce_to_object(env: NapiEnv, value: NapiValue) -> Result<NapiValue> {
+    let mut result: NapiValue = 0;
+    let status = unsafe { napi_coerce_to_object(env, value, &mut result) };
+    napi_either(env, status, result)
+}
 
 
 //     pub fn napi_coerce_to_string(env: napi_env, value: napi_value,
 //                                  result: *mut napi_value) -> napi_status;
+pub fn coerce_to_string(env: NapiEnv, value: NapiValue) -> Result<NapiValue> {
+    let mut result: NapiValue = 0;
+    let status = unsafe { napi_coerce_to_string(env, value, &mut result) };
+    napi_either(env, status, result)
+}
 
 
 //     pub fn napi_get_prototype(env: napi_env, object: napi_value,
 //                               result: *mut napi_value) -> napi_status;
+pub fn get_prototype(env: NapiEnv, object: NapiValue) -> Result<NapiValue> {
+    let mut result: NapiValue = 0;
+    let status = unsafe { napi_get_prototype(env, object, &mut result) };
+    napi_either(env, status, result)
+}
 
 
 //     pub fn napi_get_property_names(env: napi_env, object: napi_value,
 //                                    result: *mut napi_value) -> napi_status;
+pub fn get_property_names(env: NapiEnv, object: NapiValue) -> Result<NapiValue> {
+    let mut result: NapiValue = 0;
+    let status = unsafe { napi_get_property_names(env, object, &mut result) };
+    napi_either(env, status, result)
+}
+
+//     pub fn napi_get_all_property_names(env: napi_env, object: napi_value,
+//                                        key_mode: napi_key_collection_mode,
+//                                        key_filter: napi_key_filter,
+//                                        key_conversion: napi_key_conversion,
+//                                        result: *mut napi_value) -> napi_status;
+// the object's own enumerable string keys, which `JSON.stringify` serializes
+pub fn get_own_property_names(env: NapiEnv, object: NapiValue) -> Result<NapiValue> {
+    let mut result: NapiValue = 0;
+    let status = unsafe {
+        napi_get_all_property_names(env,
+                                    object,
+                                    napi_key_collection_mode::napi_key_own_only,
+                                    napi_key_filter_napi_key_enumerable |
+                                    napi_key_filter_napi_key_skip_symbols,
+                                    napi_key_conversion::napi_key_numbers_to_strings,
+                                    &mut result)
+    };
+    napi_either(env, status, result)
+}
 
 
 //     pub fn napi_set_property(env: napi_env, object: napi_value,
 //                              key: napi_value, value: napi_value)
 //      -> napi_status;
+pub fn set_property(env: NapiEnv, object: NapiValue, key: NapiValue, value: NapiValue) -> Result<()> {
+    let status = unsafe { napi_set_property(env, object, key, value) };
+    napi_either(env, status, ())
+}
 
 
 //     pub fn napi_has_property(env: napi_env, object: napi_value,
 //                              key: napi_value, result: *mut bool)
 //      -> napi_status;
+pub fn has_property(env: NapiEnv, object: NapiValue, key: NapiValue) -> Result<bool> {
+    let mut result = false;
+    let status = unsafe { napi_has_property(env, object, key, &mut result) };
+    napi_either(env, status, result)
+}
 
 
 //     pub fn napi_get_property(env: napi_env, object: napi_value,
 //                              key: napi_value, result: *mut napi_value)
 //      -> napi_status;
+pub fn get_property(env: NapiEnv, object: NapiValue, key: NapiValue) -> Result<NapiValue> {
+    let mut result: NapiValue = 0;
+    let status = unsafe { napi_get_property(env, object, key, &mut result) };
+    napi_either(env, status, result)
+}
+
+//     pub fn napi_delete_property(env: napi_env, object: napi_value,
+//                                 key: napi_value, result: *mut bool)
+//      -> napi_status;
+// returns whether the property has been deleted, which is false for
+// non-configurable properties
+pub fn delete_property(env: NapiEnv, object: NapiValue, key: NapiValue) -> Result<bool> {
+    let mut result = false;
+    let status = unsafe { napi_delete_property(env, object, key, &mut result) };
+    napi_either(env, status, result)
+}
 
 
 //     pub fn napi_set_named_property(env: napi_env, object: napi_value,
@@ -416,6 +750,11 @@ pub fn get_array_length(env: NapiEnv, value: NapiValue) -> Result<usize> {
 
 //     pub fn napi_strict_equals(env: napi_env, lhs: napi_value, rhs: napi_value,
 //                               result: *mut bool) -> napi_status;
+pub fn strict_equals(env: NapiEnv, lhs: NapiValue, rhs: NapiValue) -> Result<bool> {
+    let mut result = false;
+    let status = unsafe { napi_strict_equals(env, lhs, rhs, &mut result) };
+    napi_either(env, status, result)
+}
 
 
 //     pub fn napi_call_function(env: napi_env, recv: napi_value,
@@ -438,17 +777,85 @@ pub fn call_function(env: NapiEnv,
 //     pub fn napi_new_instance(env: napi_env, constructor: napi_value,
 //                              argc: usize, argv: *const napi_value,
 //                              result: *mut napi_value) -> napi_status;
+pub fn new_instance(env: NapiEnv, constructor: NapiValue, args: &[NapiValue]) -> Result<NapiValue> {
+    let mut result: NapiValue = 0;
+    let status =
+        unsafe { napi_new_instance(env, constructor, args.len(), args.as_ptr(), &mut result) };
+    napi_either(env, status, result)
+}
 
 
 //     pub fn napi_instanceof(env: napi_env, object: napi_value,
 //                            constructor: napi_value, result: *mut bool)
 //      -> napi_status;
+pub fn instanceof(env: NapiEnv, object: NapiValue, constructor: NapiValue) -> Result<bool> {
+    let mut result = false;
+    let status = unsafe { napi_instanceof(env, object, constructor, &mut result) };
+    napi_either(env, status, result)
+}
 
 
-//     pub fn napi_make_callback(env: napi_env, recv: napi_value,
-//                               func: napi_value, argc: usize,
+//     pub fn napi_make_callback(env: napi_env, async_context: napi_async_context,
+//                               recv: napi_value, func: napi_value, argc: usize,
 //                               argv: *const napi_value,
 //                               result: *mut napi_value) -> napi_status;
+// like `call_function`, but runs the function in the given async context and
+// processes the microtask queue afterwards, as when node itself calls into JS
+pub fn make_callback(env: NapiEnv,
+                     async_context: NapiAsyncContext,
+                     recv: NapiValue,
+                     func: NapiValue,
+                     args: &[NapiValue])
+                     -> Result<NapiValue> {
+    let mut result: NapiValue = 0;
+    let status = unsafe {
+        napi_make_callback(env,
+                           async_context,
+                           recv,
+                           func,
+                           args.len(),
+                           args.as_ptr(),
+                           &mut result)
+    };
+    napi_either(env, status, result)
+}
+
+//     pub fn napi_async_init(env: napi_env, async_resource: napi_value,
+//                            async_resource_name: napi_value,
+//                            result: *mut napi_async_context) -> napi_status;
+pub fn async_init(env: NapiEnv, resource: NapiValue, name: &str) -> Result<NapiAsyncContext> {
+    let name = create_string_utf8(env, name)?;
+    let mut result: NapiAsyncContext = ptr::null_mut();
+    let status = unsafe { napi_async_init(env, resource, name, &mut result) };
+    napi_either(env, status, result)
+}
+
+//     pub fn napi_async_destroy(env: napi_env, async_context: napi_async_context)
+//      -> napi_status;
+pub fn async_destroy(env: NapiEnv, async_context: NapiAsyncContext) -> Result<()> {
+    let status = unsafe { napi_async_destroy(env, async_context) };
+    napi_either(env, status, ())
+}
+
+//     pub fn napi_open_callback_scope(env: napi_env, resource_object: napi_value,
+//                                     context: napi_async_context,
+//                                     result: *mut napi_callback_scope)
+//      -> napi_status;
+pub fn open_callback_scope(env: NapiEnv,
+                           resource: NapiValue,
+                           async_context: NapiAsyncContext)
+                           -> Result<NapiCallbackScope> {
+    let mut result: NapiCallbackScope = ptr::null_mut();
+    let status = unsafe { napi_open_callback_scope(env, resource, async_context, &mut result) };
+    napi_either(env, status, result)
+}
+
+//     pub fn napi_close_callback_scope(env: napi_env, scope: napi_callback_scope)
+//      -> napi_status;
+pub fn close_callback_scope(env: NapiEnv, scope: NapiCallbackScope) -> Result<()> {
+    let status = unsafe { napi_close_callback_scope(env, scope) };
+    napi_either(env, status, ())
+}
 
 
 //     pub fn napi_get_cb_info(env: napi_env, cbinfo: napi_callback_info,
@@ -456,15 +863,59 @@ pub fn call_function(env: NapiEnv,
 //                             this_arg: *mut napi_value,
 //                             data: *mut *mut ::std::os::raw::c_void)
 //      -> napi_status;
+// returns `this`, all of the arguments however many there are, and the
+// callback's data
+pub fn get_cb_info(env: NapiEnv,
+                   cbinfo: napi_callback_info)
+                   -> Result<(NapiValue, Vec<NapiValue>, *mut os::raw::c_void)> {
+    let mut argc: usize = 0;
+    let mut this: NapiValue = 0;
+    let mut data = ptr::null_mut();
+    let status = unsafe {
+        napi_get_cb_info(env,
+                         cbinfo,
+                         &mut argc,
+                         ptr::null_mut(),
+                         &mut this,
+                         &mut data)
+    };
+    napi_either(env, status, ())?;
+    let mut argv: Vec<NapiValue> = vec![0; argc];
+    if argc > 0 {
+        let status = unsafe {
+            napi_get_cb_info(env,
+                             cbinfo,
+                             &mut argc,
+                             argv.as_mut_ptr(),
+                             ptr::null_mut(),
+                             ptr::null_mut())
+        };
+        napi_either(env, status, ())?;
+    }
+    Ok((this, argv, data))
+}
+
+
+// Node does not export `napi_is_construct_call`, so this checks whether there
+// is a `new.target` instead
+pub fn is_construct_call(env: NapiEnv, cbinfo: napi_callback_info) -> Result<bool> {
+    get_new_target(env, cbinfo).map(|new_target| new_target.is_some())
+}
 
 
-//     pub fn napi_is_construct_call(env: napi_env, cbinfo: napi_callback_info,
-//                                   result: *mut bool) -> napi_status;
+//     pub fn napi_get_new_target(env: napi_env, cbinfo: napi_callback_info,
+//                                result: *mut napi_value) -> napi_status;
+// `None` unless the function was called with `new`
+pub fn get_new_target(env: NapiEnv, cbinfo: napi_callback_info) -> Result<Option<NapiValue>> {
+    let mut result: NapiValue = 0;
+    let status = unsafe { napi_get_new_target(env, cbinfo, &mut result) };
+    napi_either(env, status, if result == 0 { None } else { Some(result) })
+}
 
 
 //     pub fn napi_define_class(env: napi_env,
 //                              utf8name: *const ::std::os::raw::c_char,
-//                              constructor: napi_callback,
+//                              length: usize, constructor: napi_callback,
 //                              data: *mut ::std::os::raw::c_void,
 //                              property_count: usize,
 //                              properties: *const napi_property_descriptor,
@@ -476,26 +927,44 @@ pub fn call_function(env: NapiEnv,
 //                      finalize_cb: napi_finalize,
 //                      finalize_hint: *mut ::std::os::raw::c_void,
 //                      result: *mut napi_ref) -> napi_status;
-pub fn wrap<T>(env: NapiEnv, js_object: NapiValue, native_object: Box<T>) -> Result<NapiRef> {
-    let mut result: NapiRef = unsafe { mem::uninitialized() };
+// no reference is asked for, as the one N-API would return belongs to the
+// wrap and must not be deleted before the object has been collected
+pub fn wrap<T>(env: NapiEnv, js_object: NapiValue, native_object: Box<T>) -> Result<()> {
+    let native_object = Box::into_raw(native_object);
     let status = unsafe {
         napi_wrap(env,
                   js_object,
-                  Box::into_raw(native_object) as *mut ::std::os::raw::c_void,
+                  native_object as *mut ::std::os::raw::c_void,
                   Some(finalize_box::<T>),
                   ptr::null_mut(),
-                  &mut result)
+                  ptr::null_mut())
     };
-    napi_either(env, status, result)
+    if status != napi_status::napi_ok {
+        unsafe { drop(Box::from_raw(native_object)) };
+    }
+    napi_either(env, status, ())
 }
 
 //     pub fn napi_unwrap(env: napi_env, js_object: napi_value,
 //                        result: *mut *mut ::std::os::raw::c_void)
 //      -> napi_status;
-pub fn unwrap<T>(env: NapiEnv, js_object: NapiValue) -> Result<Box<T>> {
+// the wrapped value stays owned by the object, which frees it once collected
+pub fn unwrap<T>(env: NapiEnv, js_object: NapiValue) -> Result<*mut T> {
     let mut result = ptr::null_mut();
     let status = unsafe { napi_unwrap(env, js_object, &mut result) };
-    napi_either(env, status, unsafe { Box::<T>::from_raw(result as *mut T) })
+    napi_either(env, status, result as *mut T)
+}
+
+//     pub fn napi_remove_wrap(env: napi_env, js_object: napi_value,
+//                             result: *mut *mut ::std::os::raw::c_void)
+//      -> napi_status;
+// takes the wrapped value back out of the object, which then no longer runs
+// its finalizer
+pub fn remove_wrap<T>(env: NapiEnv, js_object: NapiValue) -> Result<Box<T>> {
+    let mut result = ptr::null_mut();
+    let status = unsafe { napi_remove_wrap(env, js_object, &mut result) };
+    napi_either(env, status, ())?;
+    Ok(unsafe { Box::<T>::from_raw(result as *mut T) })
 }
 
 //     pub fn napi_create_external(env: napi_env,
@@ -516,6 +985,29 @@ pub fn create_external<T>(env: NapiEnv, t: Box<T>) -> Result<NapiValue> {
     napi_either(env, status, result)
 }
 
+//     pub fn napi_add_finalizer(env: napi_env, js_object: napi_value,
+//                               finalize_data: *mut ::std::os::raw::c_void,
+//                               finalize_cb: napi_finalize,
+//                               finalize_hint: *mut ::std::os::raw::c_void,
+//                               result: *mut napi_ref) -> napi_status;
+// ties the lifetime of `data` to `js_object`, dropping it once the object has
+// been garbage collected
+pub fn add_finalizer<T>(env: NapiEnv, js_object: NapiValue, data: Box<T>) -> Result<()> {
+    let data = Box::into_raw(data);
+    let status = unsafe {
+        napi_add_finalizer(env,
+                           js_object,
+                           data as *mut ::std::os::raw::c_void,
+                           Some(finalize_box::<T>),
+                           ptr::null_mut(),
+                           ptr::null_mut())
+    };
+    if status != napi_status::napi_ok {
+        unsafe { drop(Box::from_raw(data)) };
+    }
+    napi_either(env, status, ())
+}
+
 unsafe extern "C" fn finalize_box<T>(_env: NapiEnv,
                                      finalize_data: *mut ::std::os::raw::c_void,
                                      _finalize_hint: *mut ::std::os::raw::c_void) {
@@ -532,6 +1024,13 @@ pub fn get_value_external<T>(env: NapiEnv, value: NapiValue) -> Result<Box<T>> {
     napi_either(env, status, unsafe { Box::<T>::from_raw(result as *mut T) })
 }
 
+// like `get_value_external`, but the data stays owned by the external
+pub fn get_external_data(env: NapiEnv, value: NapiValue) -> Result<*mut os::raw::c_void> {
+    let mut result = ptr::null_mut();
+    let status = unsafe { napi_get_value_external(env, value, &mut result) };
+    napi_either(env, status, result)
+}
+
 
 //     pub fn napi_create_reference(env: napi_env, value: napi_value,
 //                                  initial_refcount: u32, result: *mut napi_ref)
@@ -582,6 +1081,15 @@ pub fn get_value_external<T>(env: NapiEnv, value: NapiValue) -> Result<Box<T>> {
 
 
 //     pub fn napi_throw(env: napi_env, error: napi_value) -> napi_status;
+pub fn throw(env: NapiEnv, error: NapiValue) -> Result<()> {
+    let status = unsafe { napi_throw(env, error) };
+    napi_either(env, status, ())
+}
+
+pub fn throw_error(env: NapiEnv, message: &str) -> Result<()> {
+    let error = create_error(env, message)?;
+    throw(env, error)
+}
 
 
 //     pub fn napi_throw_error(env: napi_env, msg: *const ::std::os::raw::c_char)
@@ -600,15 +1108,52 @@ pub fn get_value_external<T>(env: NapiEnv, value: NapiValue) -> Result<Box<T>> {
 
 //     pub fn napi_is_error(env: napi_env, value: napi_value, result: *mut bool)
 //      -> napi_status;
+pub fn is_error(env: NapiEnv, value: NapiValue) -> Result<bool> {
+    let mut result = false;
+    let status = unsafe { napi_is_error(env, value, &mut result) };
+    napi_either(env, status, result)
+}
 
 
 //     pub fn napi_is_exception_pending(env: napi_env, result: *mut bool)
 //      -> napi_status;
+pub fn is_exception_pending(env: NapiEnv) -> Result<bool> {
+    let mut result = false;
+    let status = unsafe { napi_is_exception_pending(env, &mut result) };
+    napi_either(env, status, result)
+}
 
 
 //     pub fn napi_get_and_clear_last_exception(env: napi_env,
 //                                              result: *mut napi_value)
 //      -> napi_status;
+pub fn get_and_clear_last_exception(env: NapiEnv) -> Result<NapiValue> {
+    let mut result: NapiValue = 0;
+    let status = unsafe { napi_get_and_clear_last_exception(env, &mut result) };
+    napi_either(env, status, result)
+}
+
+
+//     pub fn napi_fatal_exception(env: napi_env, err: napi_value) -> napi_status;
+// emits `uncaughtException` with `error`, like an exception thrown by a JS
+// callback that nothing catches would
+pub fn fatal_exception(env: NapiEnv, error: NapiValue) -> Result<()> {
+    let status = unsafe { napi_fatal_exception(env, error) };
+    napi_either(env, status, ())
+}
+
+// reports failures of callbacks that have no JS caller to throw to, e.g. of
+// completion callbacks, as uncaught exceptions. A pending JS exception is
+// reported instead of `err`, as it usually is what made the callback fail.
+pub fn report_uncaught(env: NapiEnv, err: NapiError) {
+    let error = match is_exception_pending(env) {
+        Ok(true) => get_and_clear_last_exception(env),
+        _ => create_error(env, &err.error_message),
+    };
+    if let Ok(error) = error {
+        let _ = fatal_exception(env, error);
+    }
+}
 
 
 //     pub fn napi_create_buffer(env: napi_env, length: usize,
@@ -630,15 +1175,38 @@ pub fn get_value_external<T>(env: NapiEnv, value: NapiValue) -> Result<Box<T>> {
 //                                    result_data:
 //                                        *mut *mut ::std::os::raw::c_void,
 //                                    result: *mut napi_value) -> napi_status;
+pub fn create_buffer_copy(env: NapiEnv, data: &[u8]) -> Result<NapiValue> {
+    let mut result: NapiValue = 0;
+    let status = unsafe {
+        napi_create_buffer_copy(env,
+                                data.len(),
+                                data.as_ptr() as *const os::raw::c_void,
+                                ptr::null_mut(),
+                                &mut result)
+    };
+    napi_either(env, status, result)
+}
 
 
 //     pub fn napi_is_buffer(env: napi_env, value: napi_value, result: *mut bool)
 //      -> napi_status;
+pub fn is_buffer(env: NapiEnv, value: NapiValue) -> Result<bool> {
+    let mut result = false;
+    let status = unsafe { napi_is_buffer(env, value, &mut result) };
+    napi_either(env, status, result)
+}
 
 
 //     pub fn napi_get_buffer_info(env: napi_env, value: napi_value,
 //                                 data: *mut *mut ::std::os::raw::c_void,
 //                                 length: *mut usize) -> napi_status;
+// returns the buffer's data pointer and length
+pub fn get_buffer_info(env: NapiEnv, value: NapiValue) -> Result<(*mut u8, usize)> {
+    let mut data = ptr::null_mut();
+    let mut length: usize = 0;
+    let status = unsafe { napi_get_buffer_info(env, value, &mut data, &mut length) };
+    napi_either(env, status, (data as *mut u8, length))
+}
 
 
 //     pub fn napi_is_arraybuffer(env: napi_env, value: napi_value,
@@ -668,6 +1236,11 @@ pub fn get_value_external<T>(env: NapiEnv, value: NapiValue) -> Result<Box<T>> {
 
 //     pub fn napi_is_typedarray(env: napi_env, value: napi_value,
 //                               result: *mut bool) -> napi_status;
+pub fn is_typedarray(env: NapiEnv, value: NapiValue) -> Result<bool> {
+    let mut result = false;
+    let status = unsafe { napi_is_typedarray(env, value, &mut result) };
+    napi_either(env, status, result)
+}
 
 
 //     pub fn napi_create_typedarray(env: napi_env, type_: napi_typedarray_type,
@@ -682,9 +1255,45 @@ pub fn get_value_external<T>(env: NapiEnv, value: NapiValue) -> Result<Box<T>> {
 //                                     data: *mut *mut ::std::os::raw::c_void,
 //                                     arraybuffer: *mut napi_value,
 //                                     byte_offset: *mut usize) -> napi_status;
+// returns the element type and the length in elements
+pub fn get_typedarray_info(env: NapiEnv, typedarray: NapiValue) -> Result<(TypedArrayType, usize)> {
+    let mut array_type = napi_typedarray_type::napi_int8_array;
+    let mut length: usize = 0;
+    let status = unsafe {
+        napi_get_typedarray_info(env,
+                                 typedarray,
+                                 &mut array_type,
+                                 &mut length,
+                                 ptr::null_mut(),
+                                 ptr::null_mut(),
+                                 ptr::null_mut())
+    };
+    napi_either(env, status, (TypedArrayType::from(array_type), length))
+}
+
+// the bytes the typed array views and their number, which stay valid for as
+// long as the array does
+pub fn get_typedarray_data(env: NapiEnv, typedarray: NapiValue) -> Result<(*const u8, usize)> {
+    let mut array_type = napi_typedarray_type::napi_int8_array;
+    let mut length: usize = 0;
+    let mut data = ptr::null_mut();
+    let status = unsafe {
+        napi_get_typedarray_info(env,
+                                 typedarray,
+                                 &mut array_type,
+                                 &mut length,
+                                 &mut data,
+                                 ptr::null_mut(),
+                                 ptr::null_mut())
+    };
+    let byte_length = length * TypedArrayType::from(array_type).element_size();
+    napi_either(env, status, (data as *const u8, byte_length))
+}
 
 
 //     pub fn napi_create_async_work(env: napi_env,
+//                                   async_resource: napi_value,
+//                                   async_resource_name: napi_value,
 //                                   execute: napi_async_execute_callback,
 //                                   complete: napi_async_complete_callback,
 //                                   data: *mut ::std::os::raw::c_void,
@@ -694,11 +1303,80 @@ pub fn get_value_external<T>(env: NapiEnv, value: NapiValue) -> Result<Box<T>> {
 
 //     pub fn napi_delete_async_work(env: napi_env, work: napi_async_work)
 //      -> napi_status;
-
+pub fn delete_async_work(env: NapiEnv, work: NapiAsyncWork) -> Result<()> {
+    let status = unsafe { napi_delete_async_work(env, work) };
+    napi_either(env, status, ())
+}
 
 //     pub fn napi_queue_async_work(env: napi_env, work: napi_async_work)
 //      -> napi_status;
-
+pub fn queue_async_work(env: NapiEnv, work: NapiAsyncWork) -> Result<()> {
+    let status = unsafe { napi_queue_async_work(env, work) };
+    napi_either(env, status, ())
+}
 
 //     pub fn napi_cancel_async_work(env: napi_env, work: napi_async_work)
 //      -> napi_status;
+pub fn cancel_async_work(env: NapiEnv, work: NapiAsyncWork) -> Result<()> {
+    let status = unsafe { napi_cancel_async_work(env, work) };
+    napi_either(env, status, ())
+}
+
+
+//     pub fn napi_create_promise(env: napi_env, deferred: *mut napi_deferred,
+//                                promise: *mut napi_value) -> napi_status;
+pub fn create_promise(env: NapiEnv) -> Result<(NapiDeferred, NapiValue)> {
+    let mut deferred: NapiDeferred = ptr::null_mut();
+    let mut promise: NapiValue = 0;
+    let status = unsafe { napi_create_promise(env, &mut deferred, &mut promise) };
+    napi_either(env, status, (deferred, promise))
+}
+
+//     pub fn napi_resolve_deferred(env: napi_env, deferred: napi_deferred,
+//                                  resolution: napi_value) -> napi_status;
+pub fn resolve_deferred(env: NapiEnv, deferred: NapiDeferred, value: NapiValue) -> Result<()> {
+    let status = unsafe { napi_resolve_deferred(env, deferred, value) };
+    napi_either(env, status, ())
+}
+
+//     pub fn napi_reject_deferred(env: napi_env, deferred: napi_deferred,
+//                                 rejection: napi_value) -> napi_status;
+pub fn reject_deferred(env: NapiEnv, deferred: NapiDeferred, value: NapiValue) -> Result<()> {
+    let status = unsafe { napi_reject_deferred(env, deferred, value) };
+    napi_either(env, status, ())
+}
+
+//     pub fn napi_is_promise(env: napi_env, value: napi_value,
+//                            is_promise: *mut bool) -> napi_status;
+pub fn is_promise(env: NapiEnv, value: NapiValue) -> Result<bool> {
+    let mut result = false;
+    let status = unsafe { napi_is_promise(env, value, &mut result) };
+    napi_either(env, status, result)
+}
+
+//     pub fn napi_create_date(env: napi_env, time: f64, result: *mut napi_value)
+//      -> napi_status;
+// `time` is in milliseconds since the epoch, values outside of the range of
+// JS dates create an invalid date
+pub fn create_date(env: NapiEnv, time: f64) -> Result<NapiValue> {
+    let mut result: NapiValue = 0;
+    let status = unsafe { napi_create_date(env, time, &mut result) };
+    napi_either(env, status, result)
+}
+
+//     pub fn napi_is_date(env: napi_env, value: napi_value, is_date: *mut bool)
+//      -> napi_status;
+pub fn is_date(env: NapiEnv, value: NapiValue) -> Result<bool> {
+    let mut result = false;
+    let status = unsafe { napi_is_date(env, value, &mut result) };
+    napi_either(env, status, result)
+}
+
+//     pub fn napi_get_date_value(env: napi_env, value: napi_value,
+//                                result: *mut f64) -> napi_status;
+// NaN for invalid dates
+pub fn get_date_value(env: NapiEnv, value: NapiValue) -> Result<f64> {
+    let mut result: f64 = 0.0;
+    let status = unsafe { napi_get_date_value(env, value, &mut result) };
+    napi_either(env, status, result)
+}
@@ -0,0 +1,293 @@
+use std::cell::RefCell;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::result::Result as StdResult;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::task::{Context, Poll, Waker};
+
+use futures::Future;
+
+use napi::{self, NapiEnv, NapiValue, NapiValueType};
+use napi_value::{FromNapiValue, IntoNapiValue};
+use napi_reference::Reference;
+use napi_threadsafe_function::{ThreadsafeFunction, CallMode};
+use error::{Result, NapiError, NapiErrorType};
+
+/// A flag that can be raised from any thread to cancel pending work.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<TokenState>,
+}
+
+#[derive(Debug, Default)]
+struct TokenState {
+    cancelled: AtomicBool,
+    wakers: Mutex<Vec<Waker>>,
+}
+
+impl CancellationToken {
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+        let wakers: Vec<Waker> = self.inner.wakers.lock().unwrap().drain(..).collect();
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    // returns whether the token has been cancelled, waking the task once it is
+    // if it has not
+    fn poll_cancelled(&self, cx: &mut Context) -> bool {
+        if self.is_cancelled() {
+            return true;
+        }
+        {
+            let mut wakers = self.inner.wakers.lock().unwrap();
+            if !wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+                wakers.push(cx.waker().clone());
+            }
+        }
+        // check again in case we were cancelled while registering
+        self.is_cancelled()
+    }
+}
+
+/// A JS `AbortSignal` passed as a function argument. Aborting it from JS
+/// cancels the associated `CancellationToken`.
+#[derive(Debug, Clone)]
+pub struct AbortSignal {
+    env: NapiEnv,
+    value: NapiValue,
+    token: CancellationToken,
+    listeners: Rc<AbortListeners>,
+}
+
+// the listeners added to a signal, held weakly so that neither the signal nor
+// the listeners are kept alive by them
+#[derive(Debug)]
+struct AbortListeners {
+    signal: Reference,
+    functions: RefCell<Vec<Reference>>,
+}
+
+impl AbortListeners {
+    fn add(&self, env: NapiEnv, signal: NapiValue, listener: NapiValue) -> Result<()> {
+        add_abort_listener(env, signal, listener)?;
+        self.functions
+            .borrow_mut()
+            .push(Reference::new(env, listener, 0)?);
+        Ok(())
+    }
+}
+
+impl AbortSignal {
+    pub fn is_aborted(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    pub fn token(&self) -> CancellationToken {
+        self.token.clone()
+    }
+
+    // calls `f` on the main thread once the signal is aborted. The signal
+    // object can only be used while the call that received it is running.
+    pub fn on_abort<F, R>(&self, f: F) -> Result<()>
+        where F: Fn() -> R + 'static,
+              R: IntoNapiValue
+    {
+        let listener = napi::create_function(self.env, "onabort", move |_, _, ()| f())?;
+        self.listeners.add(self.env, self.value, listener)
+    }
+
+    // removes the listeners added to the signal, so that they do not pile up
+    // on long-lived signals once the work they abort has completed
+    pub fn remove_listeners(&self) -> Result<()> {
+        let functions: Vec<Reference> = self.listeners.functions.borrow_mut().drain(..).collect();
+        let signal = match self.listeners.signal.value()? {
+            Some(signal) => signal,
+            None => return Ok(()),
+        };
+        let remove_event_listener = napi::get_named_property(self.env, signal, "removeEventListener")?;
+        let event_type = napi::create_string_utf8(self.env, "abort")?;
+        for function in functions {
+            if let Some(listener) = function.value()? {
+                napi::call_function(self.env,
+                                    signal,
+                                    remove_event_listener,
+                                    &[event_type, listener])?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromNapiValue for AbortSignal {
+    fn from_napi_value(env: NapiEnv, value: NapiValue) -> Result<Self> {
+        if !is_abort_signal(env, value)? {
+            return Err(NapiError {
+                           error_message: "expected an AbortSignal".to_string(),
+                           engine_error_code: 0,
+                           error_code: NapiErrorType::InvalidArg,
+                       });
+        }
+        let token = CancellationToken::new();
+        let listeners = Rc::new(AbortListeners {
+                                    signal: Reference::new(env, value, 0)?,
+                                    functions: RefCell::new(Vec::new()),
+                                });
+        let aborted = napi::get_named_property(env, value, "aborted")?;
+        if napi::get_value_bool(env, aborted)? {
+            token.cancel();
+        } else {
+            let listener_token = token.clone();
+            let listener = napi::create_function(env, "onabort", move |_, _, ()| {
+                listener_token.cancel()
+            })?;
+            listeners.add(env, value, listener)?;
+        }
+        Ok(AbortSignal {
+               env: env,
+               value: value,
+               token: token,
+               listeners: listeners,
+           })
+    }
+}
+
+// nothing is accepted by engines without a global `AbortSignal` class, as
+// objects that merely look like a signal may never abort
+fn is_abort_signal(env: NapiEnv, value: NapiValue) -> Result<bool> {
+    if napi::type_of(env, value)? != NapiValueType::Object {
+        return Ok(false);
+    }
+    let global = napi::get_global(env)?;
+    let class = napi::get_named_property(env, global, "AbortSignal")?;
+    if napi::type_of(env, class)? != NapiValueType::Function {
+        return Ok(false);
+    }
+    napi::instanceof(env, value, class)
+}
+
+fn add_abort_listener(env: NapiEnv, signal: NapiValue, listener: NapiValue) -> Result<()> {
+    let add_event_listener = napi::get_named_property(env, signal, "addEventListener")?;
+    let event_type = napi::create_string_utf8(env, "abort")?;
+    let options = napi::create_object(env)?;
+    let once = napi::get_boolean(env, true)?;
+    napi::set_named_property(env, options, "once", once)?;
+    napi::call_function(env,
+                        signal,
+                        add_event_listener,
+                        &[event_type, listener, options])?;
+    Ok(())
+}
+
+/// The rejection value of aborted operations, converted into a JS `Error`
+/// named `AbortError` like the ones node's own APIs reject with.
+#[derive(Debug, Clone, Copy)]
+pub struct AbortError;
+
+impl IntoNapiValue for AbortError {
+    fn into_napi_value(self, env: NapiEnv) -> Result<NapiValue> {
+        let error = napi::create_error(env, "The operation was aborted")?;
+        let name = napi::create_string_utf8(env, "AbortError")?;
+        let code = napi::create_string_utf8(env, "ABORT_ERR")?;
+        napi::set_named_property(env, error, "name", name)?;
+        napi::set_named_property(env, error, "code", code)?;
+        Ok(error)
+    }
+}
+
+#[derive(Debug)]
+pub enum AbortableError<E> {
+    Aborted,
+    Failed(E),
+}
+
+impl<E> IntoNapiValue for AbortableError<E>
+    where E: IntoNapiValue
+{
+    fn into_napi_value(self, env: NapiEnv) -> Result<NapiValue> {
+        match self {
+            AbortableError::Aborted => AbortError.into_napi_value(env),
+            AbortableError::Failed(err) => err.into_napi_value(env),
+        }
+    }
+}
+
+/// A future that is dropped without completing once its token is cancelled.
+pub struct Abortable<F> {
+    future: Pin<Box<F>>,
+    token: Option<CancellationToken>,
+    // removes the signal's listeners on the main thread once dropped
+    remove_listeners: Option<ThreadsafeFunction<()>>,
+}
+
+impl<F> Abortable<F> {
+    pub fn new(future: F, token: Option<CancellationToken>) -> Abortable<F> {
+        Abortable {
+            future: Box::pin(future),
+            token: token,
+            remove_listeners: None,
+        }
+    }
+}
+
+impl<F> Drop for Abortable<F> {
+    fn drop(&mut self) {
+        if let Some(remove_listeners) = self.remove_listeners.take() {
+            // fails only if the env is shutting down, along with the signal
+            let _ = remove_listeners.call((), CallMode::NonBlocking);
+        }
+    }
+}
+
+impl<F, T, E> Future for Abortable<F>
+    where F: Future<Output = StdResult<T, E>>
+{
+    type Output = StdResult<T, AbortableError<E>>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let aborted = match self.token {
+            Some(ref token) => token.poll_cancelled(cx),
+            None => false,
+        };
+        if aborted {
+            return Poll::Ready(Err(AbortableError::Aborted));
+        }
+        self.future
+            .as_mut()
+            .poll(cx)
+            .map(|result| result.map_err(AbortableError::Failed))
+    }
+}
+
+// makes `future` abortable through `signal` if one has been passed. The
+// signal's listeners are removed once the future has completed or been
+// dropped, or stay until the signal is aborted if that cannot be arranged.
+pub fn abortable<F>(future: F, signal: Option<&AbortSignal>) -> Abortable<F> {
+    let mut abortable = Abortable::new(future, signal.map(AbortSignal::token));
+    abortable.remove_listeners = signal.and_then(|signal| listener_remover(signal).ok());
+    abortable
+}
+
+// a function that can be called from any thread to remove the listeners of
+// `signal`, which does not keep the event loop alive
+fn listener_remover(signal: &AbortSignal) -> Result<ThreadsafeFunction<()>> {
+    let env = signal.env;
+    let signal = signal.clone();
+    let remove_listeners = napi::create_function(env,
+                                                 "removeAbortListeners",
+                                                 move |_, _, ()| signal.remove_listeners())?;
+    let remove_listeners =
+        ThreadsafeFunction::with_converter(env, remove_listeners, 0, |_, ()| Ok(Vec::new()))?;
+    remove_listeners.unref(env)?;
+    Ok(remove_listeners)
+}
@@ -0,0 +1,76 @@
+use napi::{self, NapiEnv, NapiValue, NapiAsyncContext, NapiCallbackScope};
+use napi_reference::Reference;
+use error::Result;
+
+/// An async context for calling into JS from outside of a JS call, e.g. from
+/// completion callbacks. Created while a JS call is running, it makes
+/// `AsyncLocalStorage` and async hooks see later callbacks as continuations of
+/// that call.
+#[derive(Debug)]
+pub struct AsyncContext {
+    env: NapiEnv,
+    context: NapiAsyncContext,
+    // kept alive until the context is destroyed, as async hooks refer to it
+    resource: Reference,
+}
+
+impl AsyncContext {
+    // `name` identifies the resource in async hooks
+    pub fn new(env: NapiEnv, name: &str) -> Result<AsyncContext> {
+        let resource = napi::create_object(env)?;
+        AsyncContext::with_resource(env, resource, name)
+    }
+
+    pub fn with_resource(env: NapiEnv, resource: NapiValue, name: &str) -> Result<AsyncContext> {
+        let reference = Reference::new(env, resource, 1)?;
+        let context = napi::async_init(env, resource, name)?;
+        Ok(AsyncContext {
+               env: env,
+               context: context,
+               resource: reference,
+           })
+    }
+
+    // calls `func` in this context, see `napi::make_callback`
+    pub fn make_callback(&self,
+                         recv: NapiValue,
+                         func: NapiValue,
+                         args: &[NapiValue])
+                         -> Result<NapiValue> {
+        napi::make_callback(self.env, self.context, recv, func, args)
+    }
+
+    // enters the context until the returned scope is dropped, for code that
+    // calls into JS without `make_callback`, e.g. by settling promises
+    pub fn enter(&self) -> Result<CallbackScope> {
+        let resource = match self.resource.value()? {
+            Some(resource) => resource,
+            None => napi::create_object(self.env)?,
+        };
+        let scope = napi::open_callback_scope(self.env, resource, self.context)?;
+        Ok(CallbackScope {
+               env: self.env,
+               scope: scope,
+           })
+    }
+}
+
+impl Drop for AsyncContext {
+    fn drop(&mut self) {
+        let _ = napi::async_destroy(self.env, self.context);
+    }
+}
+
+/// Closes the callback scope when dropped, which runs the microtasks queued
+/// inside of it.
+#[derive(Debug)]
+pub struct CallbackScope {
+    env: NapiEnv,
+    scope: NapiCallbackScope,
+}
+
+impl Drop for CallbackScope {
+    fn drop(&mut self) {
+        let _ = napi::close_callback_scope(self.env, self.scope);
+    }
+}
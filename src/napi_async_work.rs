@@ -0,0 +1,233 @@
+use std::boxed::Box;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::{ptr, os};
+
+use node_api_sys::*;
+
+use napi::{self, NapiEnv, NapiValue, NapiAsyncWork};
+use napi_value::IntoNapiValue;
+use napi_promise::{Deferred, Promise};
+use napi_abort::{AbortSignal, AbortError};
+use error::{Result, NapiError, NapiErrorType};
+
+/// A unit of work that is computed on the libuv threadpool and whose output
+/// is converted into a JS value back on the main thread.
+pub trait AsyncTask: Send + Sized + 'static {
+    type Output: Send + 'static;
+    type JsValue: IntoNapiValue;
+
+    // runs on a threadpool thread, must not call into N-API
+    fn compute(self) -> Result<Self::Output>;
+
+    // runs on the main thread once `compute` has finished
+    fn resolve(env: NapiEnv, output: Self::Output) -> Result<Self::JsValue>;
+}
+
+/// Wraps an `AsyncTask` so that returning it from a function queues the task
+/// and hands a `Promise` for its result to JS.
+pub struct Task<T: AsyncTask>(pub T);
+
+impl<T> IntoNapiValue for Task<T>
+    where T: AsyncTask
+{
+    fn into_napi_value(self, env: NapiEnv) -> Result<NapiValue> {
+        let (promise, _) = queue_async_task(env, self.0)?;
+        promise.into_napi_value(env)
+    }
+}
+
+/// Like `Task`, but rejects the promise with an `AbortError` once the signal
+/// is aborted.
+pub struct AbortableTask<T: AsyncTask>(pub T, pub Option<AbortSignal>);
+
+impl<T> IntoNapiValue for AbortableTask<T>
+    where T: AsyncTask
+{
+    fn into_napi_value(self, env: NapiEnv) -> Result<NapiValue> {
+        let (promise, _) = queue_abortable_task(env, self.0, self.1.as_ref())?;
+        promise.into_napi_value(env)
+    }
+}
+
+/// Handle to queued async work, used to cancel it before it starts running.
+#[derive(Debug, Clone)]
+pub struct AsyncWork {
+    env: NapiEnv,
+    // reset to null once the work has completed and been deleted
+    work: Rc<Cell<NapiAsyncWork>>,
+    // taken by whoever settles the promise first
+    deferred: Rc<RefCell<Option<Deferred>>>,
+}
+
+impl AsyncWork {
+    pub fn cancel(&self) -> Result<()> {
+        let work = self.work.get();
+        if work.is_null() {
+            Err(NapiError {
+                    error_message: "async work has already completed".to_string(),
+                    engine_error_code: 0,
+                    error_code: NapiErrorType::GenericFailure,
+                })
+        } else {
+            napi::cancel_async_work(self.env, work)
+        }
+    }
+
+    pub fn is_completed(&self) -> bool {
+        self.work.get().is_null()
+    }
+
+    // rejects the promise with an `AbortError` right away. Work that is still
+    // queued is cancelled, while work that is already running cannot be
+    // stopped and its output is dropped once it is done.
+    pub fn abort(&self) -> Result<()> {
+        let deferred = self.deferred.borrow_mut().take();
+        match deferred {
+            Some(deferred) => {
+                // fails if the work is already running
+                let _ = self.cancel();
+                deferred.reject(AbortError)
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+struct AsyncTaskState<T: AsyncTask> {
+    task: Option<T>,
+    output: Option<Result<T::Output>>,
+    deferred: Rc<RefCell<Option<Deferred>>>,
+    work: Rc<Cell<NapiAsyncWork>>,
+    signal: Option<AbortSignal>,
+}
+
+pub fn queue_abortable_task<T>(env: NapiEnv,
+                               task: T,
+                               signal: Option<&AbortSignal>)
+                               -> Result<(Promise, AsyncWork)>
+    where T: AsyncTask
+{
+    match signal {
+        Some(signal) if signal.is_aborted() => {
+            let (deferred, promise) = Deferred::new(env)?;
+            deferred.reject(AbortError)?;
+            Ok((promise,
+                AsyncWork {
+                    env: env,
+                    work: Rc::new(Cell::new(ptr::null_mut())),
+                    deferred: Rc::new(RefCell::new(None)),
+                }))
+        }
+        Some(signal) => {
+            let (promise, work) = queue_task(env, task, Some(signal.clone()))?;
+            let abort_work = work.clone();
+            signal.on_abort(move || abort_work.abort())?;
+            Ok((promise, work))
+        }
+        None => queue_async_task(env, task),
+    }
+}
+
+pub fn queue_async_task<T>(env: NapiEnv, task: T) -> Result<(Promise, AsyncWork)>
+    where T: AsyncTask
+{
+    queue_task(env, task, None)
+}
+
+// the listeners of `signal` are removed once the task has completed
+fn queue_task<T>(env: NapiEnv,
+                 task: T,
+                 signal: Option<AbortSignal>)
+                 -> Result<(Promise, AsyncWork)>
+    where T: AsyncTask
+{
+    let (deferred, promise) = Deferred::new(env)?;
+    let work = Rc::new(Cell::new(ptr::null_mut()));
+    let deferred = Rc::new(RefCell::new(Some(deferred)));
+    let state = Box::into_raw(Box::new(AsyncTaskState {
+                                           task: Some(task),
+                                           output: None,
+                                           deferred: deferred.clone(),
+                                           work: work.clone(),
+                                           signal: signal,
+                                       }));
+
+    let name = napi::create_string_utf8(env, "node_api::AsyncTask")?;
+    let mut raw_work: NapiAsyncWork = ptr::null_mut();
+    let status = unsafe {
+        napi_create_async_work(env,
+                               0,
+                               name,
+                               Some(execute_task::<T>),
+                               Some(complete_task::<T>),
+                               state as *mut os::raw::c_void,
+                               &mut raw_work)
+    };
+    if let Err(err) = napi::napi_either(env, status, ()) {
+        unsafe { drop(Box::from_raw(state)) };
+        return Err(err);
+    }
+    work.set(raw_work);
+
+    if let Err(err) = napi::queue_async_work(env, raw_work) {
+        work.set(ptr::null_mut());
+        napi::delete_async_work(env, raw_work)?;
+        unsafe { drop(Box::from_raw(state)) };
+        return Err(err);
+    }
+
+    Ok((promise,
+        AsyncWork {
+            env: env,
+            work: work,
+            deferred: deferred,
+        }))
+}
+
+unsafe extern "C" fn execute_task<T>(_env: NapiEnv, data: *mut os::raw::c_void)
+    where T: AsyncTask
+{
+    let state = &mut *(data as *mut AsyncTaskState<T>);
+    if let Some(task) = state.task.take() {
+        state.output = Some(task.compute());
+    }
+}
+
+unsafe extern "C" fn complete_task<T>(env: NapiEnv,
+                                      status: napi_status,
+                                      data: *mut os::raw::c_void)
+    where T: AsyncTask
+{
+    let mut state: Box<AsyncTaskState<T>> = Box::from_raw(data as *mut AsyncTaskState<T>);
+    let work = state.work.replace(ptr::null_mut());
+    // failures are reported as uncaught exceptions, as panicking here would
+    // abort the process
+    if let Err(err) = napi::delete_async_work(env, work) {
+        napi::report_uncaught(env, err);
+    }
+    if let Some(signal) = state.signal.take() {
+        if let Err(err) = signal.remove_listeners() {
+            napi::report_uncaught(env, err);
+        }
+    }
+
+    // the promise has already been rejected if the work was aborted
+    let deferred = match state.deferred.borrow_mut().take() {
+        Some(deferred) => deferred,
+        None => return,
+    };
+    let settled = match (status, state.output.take()) {
+        (napi_status::napi_ok, Some(Ok(output))) => {
+            match T::resolve(env, output) {
+                Ok(value) => deferred.resolve(value),
+                Err(err) => deferred.reject(err),
+            }
+        }
+        (napi_status::napi_ok, Some(Err(err))) => deferred.reject(err),
+        (status, _) => deferred.reject(NapiError::from(status)),
+    };
+    if let Err(err) = settled {
+        napi::report_uncaught(env, err);
+    }
+}
@@ -0,0 +1,170 @@
+use std::any::Any;
+use std::rc::Rc;
+use std::{ptr, os};
+
+use node_api_sys::napi_callback_info;
+
+use napi::{self, NapiEnv, NapiValue};
+use napi_value::{FromNapiValue, FromNapiValues};
+use napi_js_value::JsValue;
+use error::Result;
+
+/// Everything a callback has been called with: `this`, all of the arguments,
+/// `new.target` and the callback's data. Function arguments are converted
+/// from it through `FromNapiValues::from_call_context`, and functions taking a
+/// `CallContext` as their arguments receive it as is, e.g. to behave
+/// differently when called with `new`.
+#[derive(Debug, Clone)]
+pub struct CallContext {
+    env: NapiEnv,
+    this: NapiValue,
+    args: Vec<NapiValue>,
+    new_target: Option<NapiValue>,
+    data: *mut os::raw::c_void,
+    function_data: Option<Rc<Any>>,
+}
+
+impl CallContext {
+    pub fn new(env: NapiEnv, cbinfo: napi_callback_info) -> Result<CallContext> {
+        let (this, args, data) = napi::get_cb_info(env, cbinfo)?;
+        let new_target = napi::get_new_target(env, cbinfo)?;
+        Ok(CallContext {
+               env: env,
+               this: this,
+               args: args,
+               new_target: new_target,
+               data: data,
+               function_data: None,
+           })
+    }
+
+    pub(crate) fn with_function_data(mut self, data: Option<Rc<Any>>) -> CallContext {
+        self.function_data = data;
+        self
+    }
+
+    pub fn env(&self) -> NapiEnv {
+        self.env
+    }
+
+    // same as `napi::is_construct_call`
+    pub fn is_construct_call(&self) -> bool {
+        self.new_target.is_some()
+    }
+
+    // `None` unless the function was called with `new`
+    pub fn new_target(&self) -> Option<JsValue> {
+        self.new_target.map(|value| JsValue::new(self.env, value))
+    }
+
+    // converts `this`, e.g. into a `JsObject`
+    pub fn this<T>(&self) -> Result<T>
+        where T: FromNapiValue
+    {
+        T::from_napi_value(self.env, self.this)
+    }
+
+    pub fn raw_this(&self) -> NapiValue {
+        self.this
+    }
+
+    // converts the arguments like they would be for a function taking `T`
+    pub fn args_as<T>(&self) -> Result<T>
+        where T: FromNapiValues
+    {
+        T::from_call_context(self)
+    }
+
+    pub fn args(&self) -> &[NapiValue] {
+        &self.args
+    }
+
+    // the number of arguments the function has been called with
+    pub fn argc(&self) -> usize {
+        self.args.len()
+    }
+
+    // the raw data pointer the callback was created with, e.g. for callbacks
+    // passed to N-API directly. For functions created by this crate it points
+    // to the crate's own data, see `function_data` for theirs.
+    pub fn data(&self) -> *mut os::raw::c_void {
+        self.data
+    }
+
+    // the data attached with `create_function_with_data`, if it is a `D`
+    pub fn function_data<D>(&self) -> Option<&D>
+        where D: Any
+    {
+        self.function_data.as_ref().and_then(|data| data.downcast_ref())
+    }
+}
+
+impl FromNapiValues for CallContext {
+    // `new.target` and the data are only known when called from JS
+    fn from_napi_values(env: NapiEnv, this: NapiValue, napi_values: &[NapiValue]) -> Result<Self> {
+        Ok(CallContext {
+               env: env,
+               this: this,
+               args: napi_values.to_vec(),
+               new_target: None,
+               data: ptr::null_mut(),
+               function_data: None,
+           })
+    }
+
+    fn from_call_context(context: &CallContext) -> Result<Self> {
+        Ok(context.clone())
+    }
+}
+
+/// All of the arguments of a call as they are, however many there are.
+#[derive(Debug, Clone)]
+pub struct Arguments {
+    this: JsValue,
+    values: Vec<JsValue>,
+    new_target: Option<JsValue>,
+}
+
+impl Arguments {
+    pub fn this(&self) -> JsValue {
+        self.this
+    }
+
+    pub fn values(&self) -> &[JsValue] {
+        &self.values
+    }
+
+    pub fn get(&self, index: usize) -> Option<JsValue> {
+        self.values.get(index).cloned()
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    // `None` unless the function was called with `new`
+    pub fn new_target(&self) -> Option<JsValue> {
+        self.new_target
+    }
+}
+
+impl FromNapiValues for Arguments {
+    fn from_napi_values(env: NapiEnv, this: NapiValue, napi_values: &[NapiValue]) -> Result<Self> {
+        Ok(Arguments {
+               this: JsValue::new(env, this),
+               values: napi_values.iter().map(|value| JsValue::new(env, *value)).collect(),
+               new_target: None,
+           })
+    }
+
+    fn from_call_context(context: &CallContext) -> Result<Self> {
+        let mut arguments =
+            Arguments::from_napi_values(context.env(), context.raw_this(), context.args())?;
+        arguments.new_target = context.new_target();
+        Ok(arguments)
+    }
+}
@@ -0,0 +1,107 @@
+use std::fmt;
+use std::marker::PhantomData;
+
+use napi::{NapiEnv, NapiValue};
+use napi_value::{FromNapiValue, IntoNapiValues};
+use napi_js_value::JsValue;
+use napi_js_types::JsFunction;
+use napi_reference::Reference;
+use napi_threadsafe_function::ThreadsafeFunction;
+use error::{Result, NapiError, NapiErrorType};
+
+/// A JS function received as an argument, called with the elements of an `A`
+/// tuple and returning an `R`. Like the other arguments it can only be used
+/// while the call that received it is running, unless it is turned into a
+/// `PersistentCallback` or a thread-safe `ThreadsafeFunction`.
+pub struct JsCallback<A, R> {
+    function: JsFunction,
+    _types: PhantomData<fn(A) -> R>,
+}
+
+impl<A, R> JsCallback<A, R>
+    where A: IntoNapiValues,
+          R: FromNapiValue
+{
+    // calls the function with `undefined` as `this`
+    pub fn call(&self, args: A) -> Result<R> {
+        self.function.call((), args)
+    }
+
+    pub fn function(&self) -> JsFunction {
+        self.function
+    }
+
+    // keeps the function alive until the returned callback is dropped
+    pub fn persist(&self) -> Result<PersistentCallback<A, R>> {
+        Ok(PersistentCallback {
+               env: self.function.env(),
+               reference: Reference::new(self.function.env(), self.function.raw(), 1)?,
+               _types: PhantomData,
+           })
+    }
+}
+
+impl<A, R> JsCallback<A, R>
+    where A: IntoNapiValues + Send + 'static
+{
+    // the function's return values are dropped, as it is invoked asynchronously
+    // on the main thread. A `max_queue_size` of 0 means the queue is unbounded.
+    pub fn into_threadsafe(self, max_queue_size: usize) -> Result<ThreadsafeFunction<A>> {
+        ThreadsafeFunction::with_converter(self.function.env(),
+                                           self.function.raw(),
+                                           max_queue_size,
+                                           |env, args: A| args.into_napi_values(env))
+    }
+}
+
+impl<A, R> FromNapiValue for JsCallback<A, R> {
+    fn from_napi_value(env: NapiEnv, value: NapiValue) -> Result<Self> {
+        Ok(JsCallback {
+               function: JsValue::new(env, value).downcast()?,
+               _types: PhantomData,
+           })
+    }
+}
+
+impl<A, R> fmt::Debug for JsCallback<A, R> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("JsCallback")
+            .field("function", &self.function)
+            .finish()
+    }
+}
+
+/// A callback that can be called after the call that received it has
+/// returned, on the main thread of the env it belongs to.
+pub struct PersistentCallback<A, R> {
+    env: NapiEnv,
+    reference: Reference,
+    _types: PhantomData<fn(A) -> R>,
+}
+
+impl<A, R> PersistentCallback<A, R>
+    where A: IntoNapiValues,
+          R: FromNapiValue
+{
+    pub fn call(&self, args: A) -> Result<R> {
+        let function = self.reference
+            .value()?
+            .ok_or_else(|| {
+                            NapiError {
+                                error_message: "callback has been released".to_string(),
+                                engine_error_code: 0,
+                                error_code: NapiErrorType::GenericFailure,
+                            }
+                        })?;
+        let function: JsFunction = JsValue::new(self.env, function).downcast()?;
+        function.call((), args)
+    }
+}
+
+impl<A, R> fmt::Debug for PersistentCallback<A, R> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PersistentCallback")
+            .field("reference", &self.reference)
+            .finish()
+    }
+}
@@ -0,0 +1,187 @@
+use std::any::TypeId;
+use std::boxed::Box;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::sync::{Mutex, MutexGuard};
+use std::{ptr, os};
+
+use node_api_sys::*;
+
+use napi::{self, NapiEnv, NapiValue};
+use napi_value::{FromNapiValue, FromNapiValues, IntoNapiValue, IntoReturnValue};
+use napi_js_value::JsValue;
+use napi_call_context::CallContext;
+use napi_property::{PropertyDescriptor, define_properties};
+use error::{Result, NapiError, NapiErrorType};
+
+/// A Rust type exposed to JS as a class. `new` on the JS class calls
+/// `constructor` and the returned value is wrapped into the new object, where
+/// it lives until the object is garbage collected.
+pub trait Class: Sized + 'static {
+    const NAME: &'static str;
+    type Args: FromNapiValues;
+
+    fn constructor(env: NapiEnv, this: NapiValue, args: Self::Args) -> Result<Self>;
+
+    // properties defined on the prototype, see `PropertyDescriptor::instance_method`
+    fn properties() -> Vec<PropertyDescriptor> {
+        Vec::new()
+    }
+}
+
+// the class instances that are alive, by address. Any wrapped object may be
+// passed as `this`, so a wrapped pointer is only used once it has been found
+// here with the type id of the class.
+static INSTANCES: Mutex<BTreeMap<usize, TypeId>> = Mutex::new(BTreeMap::new());
+
+type Instance<T> = RefCell<T>;
+
+fn instances() -> MutexGuard<'static, BTreeMap<usize, TypeId>> {
+    INSTANCES.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+// the prototype's properties are defined once the class exists, rather than
+// through `napi_define_class`, so that its methods and accessors own their
+// callbacks like those of `define_properties` do and keep working after the
+// constructor has been collected
+pub fn define_class<T>(env: NapiEnv) -> Result<NapiValue>
+    where T: Class
+{
+    let mut constructor: NapiValue = 0;
+    let status = unsafe {
+        napi_define_class(env,
+                          T::NAME.as_ptr() as *const os::raw::c_char,
+                          T::NAME.len(),
+                          Some(construct::<T>),
+                          ptr::null_mut(),
+                          0,
+                          ptr::null(),
+                          &mut constructor)
+    };
+    napi::napi_either(env, status, ())?;
+    let prototype = napi::get_named_property(env, constructor, "prototype")?;
+    define_properties(env, prototype, T::properties())?;
+    Ok(constructor)
+}
+
+// calls `f` with the instance wrapped into `this`, failing if `this` is not an
+// instance of `T` or if the instance is already borrowed further up the stack
+pub fn with_instance<T, F, R>(env: NapiEnv, this: NapiValue, f: F) -> Result<R>
+    where T: Class,
+          F: FnOnce(&mut T) -> R
+{
+    let mut result = ptr::null_mut();
+    let status = unsafe { napi_unwrap(env, this, &mut result) };
+    napi::napi_either(env, status, ())
+        .map_err(|_| invalid_instance::<T>())?;
+    if instances().get(&(result as usize)) != Some(&TypeId::of::<T>()) {
+        return Err(invalid_instance::<T>());
+    }
+    let instance = unsafe { &*(result as *const Instance<T>) };
+    let mut value = instance
+        .try_borrow_mut()
+        .map_err(|_| {
+                     NapiError {
+                         error_message: format!("{} instance is already in use", T::NAME),
+                         engine_error_code: 0,
+                         error_code: NapiErrorType::GenericFailure,
+                     }
+                 })?;
+    Ok(f(&mut value))
+}
+
+fn invalid_instance<T: Class>() -> NapiError {
+    NapiError {
+        error_message: format!("expected an instance of {}", T::NAME),
+        engine_error_code: 0,
+        error_code: NapiErrorType::InvalidArg,
+    }
+}
+
+impl PropertyDescriptor {
+    pub fn instance_method<C, F, T, R, M>(name: &str, f: F) -> PropertyDescriptor
+        where C: Class,
+              F: Fn(&mut C, NapiEnv, T) -> R + 'static,
+              T: FromNapiValues,
+              R: IntoReturnValue<M>
+    {
+        PropertyDescriptor::method(name, move |env, this, args: T| -> Result<JsValue> {
+            let returned = with_instance(env, this, |instance: &mut C| f(instance, env, args))?;
+            returned.into_return_value(env).map(|value| JsValue::new(env, value))
+        })
+    }
+
+    pub fn instance_getter<C, F, R>(name: &str, f: F) -> PropertyDescriptor
+        where C: Class,
+              F: Fn(&mut C, NapiEnv) -> R + 'static,
+              R: IntoNapiValue
+    {
+        PropertyDescriptor::getter(name, move |env, this| {
+            with_instance(env, this, |instance: &mut C| f(instance, env))
+        })
+    }
+
+    // a failed conversion or a `this` that is not an instance is thrown
+    pub fn instance_setter<C, F, T>(self, f: F) -> PropertyDescriptor
+        where C: Class,
+              F: Fn(&mut C, NapiEnv, T) + 'static,
+              T: FromNapiValue
+    {
+        self.fallible_setter(move |env, this, value: T| {
+                                 with_instance(env, this, |instance: &mut C| f(instance, env, value))
+                             })
+    }
+}
+
+unsafe extern "C" fn construct<T>(env: NapiEnv, cbinfo: napi_callback_info) -> NapiValue
+    where T: Class
+{
+    match construct_instance::<T>(env, cbinfo) {
+        Ok(this) => this,
+        Err(err) => {
+            let _ = err.into_napi_value(env).and_then(|error| napi::throw(env, error));
+            0
+        }
+    }
+}
+
+unsafe fn construct_instance<T>(env: NapiEnv, cbinfo: napi_callback_info) -> Result<NapiValue>
+    where T: Class
+{
+    let context = CallContext::new(env, cbinfo)?;
+    if !context.is_construct_call() {
+        return Err(NapiError {
+                       error_message: format!("class constructor {} cannot be invoked \
+                                               without 'new'",
+                                              T::NAME),
+                       engine_error_code: 0,
+                       error_code: NapiErrorType::FunctionExpected,
+                   });
+    }
+    let this = context.raw_this();
+    let args = T::Args::from_call_context(&context)?;
+    let value = T::constructor(env, this, args)?;
+    let instance: *mut Instance<T> = Box::into_raw(Box::new(RefCell::new(value)));
+    instances().insert(instance as usize, TypeId::of::<T>());
+    let status = napi_wrap(env,
+                           this,
+                           instance as *mut os::raw::c_void,
+                           Some(finalize_instance::<T>),
+                           ptr::null_mut(),
+                           ptr::null_mut());
+    if let Err(err) = napi::napi_either(env, status, ()) {
+        instances().remove(&(instance as usize));
+        drop(Box::from_raw(instance));
+        return Err(err);
+    }
+    Ok(this)
+}
+
+unsafe extern "C" fn finalize_instance<T>(_env: NapiEnv,
+                                          finalize_data: *mut os::raw::c_void,
+                                          _finalize_hint: *mut os::raw::c_void)
+    where T: Class
+{
+    instances().remove(&(finalize_data as usize));
+    drop(Box::from_raw(finalize_data as *mut Instance<T>));
+}
@@ -0,0 +1,104 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, TimeZone, Utc};
+
+use napi::{self, NapiEnv, NapiValue};
+use napi_value::{FromNapiValue, FromNapiValues, IntoNapiValue};
+use napi_js_value::JsValue;
+use napi_js_types::JsDate;
+use error::{Result, NapiError, NapiErrorType};
+
+// JS dates range over 100,000,000 days before and after the epoch
+const MAX_TIME: f64 = 8.64e15;
+
+fn invalid_date(message: &str) -> NapiError {
+    NapiError {
+        error_message: message.to_string(),
+        engine_error_code: 0,
+        error_code: NapiErrorType::InvalidArg,
+    }
+}
+
+// the milliseconds since the epoch of a valid `Date`, rounded down so that
+// times before the epoch are converted the same way by every conversion
+fn date_time(env: NapiEnv, value: NapiValue) -> Result<f64> {
+    let time = JsValue::new(env, value).downcast::<JsDate>()?.time()?;
+    if time.is_nan() {
+        Err(invalid_date("invalid Date"))
+    } else {
+        Ok(time.floor())
+    }
+}
+
+fn create_date(env: NapiEnv, time: f64) -> Result<NapiValue> {
+    if time.abs() > MAX_TIME {
+        return Err(invalid_date("time is out of the range of Date"));
+    }
+    napi::create_date(env, time)
+}
+
+// `Date`s only have millisecond precision, so times are rounded down to whole
+// milliseconds like chrono's `timestamp_millis` does
+impl IntoNapiValue for SystemTime {
+    fn into_napi_value(self, env: NapiEnv) -> Result<NapiValue> {
+        let time = match self.duration_since(UNIX_EPOCH) {
+            Ok(duration) => duration_millis(duration),
+            Err(err) => -duration_millis(err.duration()),
+        };
+        create_date(env, time.floor())
+    }
+}
+
+fn duration_millis(duration: Duration) -> f64 {
+    duration.as_secs() as f64 * 1000.0 + duration.subsec_nanos() as f64 / 1_000_000.0
+}
+
+impl FromNapiValue for SystemTime {
+    fn from_napi_value(env: NapiEnv, value: NapiValue) -> Result<Self> {
+        let time = date_time(env, value)?;
+        let duration = Duration::from_millis(time.abs() as u64);
+        // `SystemTime` has a narrower range than `Date` on some platforms,
+        // e.g. it starts in 1601 on Windows
+        let system_time = if time < 0.0 {
+            UNIX_EPOCH.checked_sub(duration)
+        } else {
+            UNIX_EPOCH.checked_add(duration)
+        };
+        system_time.ok_or_else(|| invalid_date("time is out of the range of SystemTime"))
+    }
+}
+
+impl FromNapiValues for SystemTime {
+    fn from_napi_values(env: NapiEnv, _: NapiValue, napi_values: &[NapiValue]) -> Result<Self> {
+        let undefined = napi::get_undefined(env)?;
+        SystemTime::from_napi_value(env, *napi_values.get(0).unwrap_or(&undefined))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl<Tz> IntoNapiValue for DateTime<Tz>
+    where Tz: TimeZone
+{
+    fn into_napi_value(self, env: NapiEnv) -> Result<NapiValue> {
+        create_date(env, self.timestamp_millis() as f64)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl FromNapiValue for DateTime<Utc> {
+    fn from_napi_value(env: NapiEnv, value: NapiValue) -> Result<Self> {
+        let time = date_time(env, value)?;
+        Utc.timestamp_millis_opt(time as i64)
+            .single()
+            .ok_or_else(|| invalid_date("time is out of the range of DateTime"))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl FromNapiValues for DateTime<Utc> {
+    fn from_napi_values(env: NapiEnv, _: NapiValue, napi_values: &[NapiValue]) -> Result<Self> {
+        let undefined = napi::get_undefined(env)?;
+        DateTime::from_napi_value(env, *napi_values.get(0).unwrap_or(&undefined))
+    }
+}
@@ -0,0 +1,253 @@
+use std::any::{Any, TypeId};
+use std::boxed::Box;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::{ptr, os};
+
+use node_api_sys::*;
+
+use napi::{self, NapiEnv};
+use error::{Result, NapiError};
+
+/// Per-env state. A module loaded into several `worker_threads` gets a
+/// separate env, and with it separate instance data, for each of them.
+pub trait NapiEnvExt {
+    // returns the env's value of type `T`, creating a default one on first use.
+    // The value is dropped when the env shuts down.
+    fn instance_data<T>(&self) -> Result<Rc<T>> where T: Default + 'static;
+
+    // replaces the env's value of type `T`
+    fn set_instance_data<T>(&self, value: T) -> Result<Rc<T>> where T: 'static;
+
+    // runs `hook` on the main thread when the env shuts down
+    fn add_cleanup_hook<F>(&self, hook: F) -> Result<CleanupHook> where F: FnOnce() + 'static;
+
+    // like `add_cleanup_hook`, but the env waits with shutting down until the
+    // `AsyncCleanup` passed to `hook` has been dropped
+    fn add_async_cleanup_hook<F>(&self, hook: F) -> Result<AsyncCleanupHook>
+        where F: FnOnce(AsyncCleanup) + 'static;
+}
+
+impl NapiEnvExt for NapiEnv {
+    fn instance_data<T>(&self) -> Result<Rc<T>>
+        where T: Default + 'static
+    {
+        let data = get_instance_data(*self)?;
+        if let Some(value) = data.get::<T>() {
+            return Ok(value);
+        }
+        // created without holding a borrow, as `T::default` may itself access
+        // instance data
+        let value: Rc<Any> = Rc::new(T::default());
+        let mut values = data.values.borrow_mut();
+        let value = values.entry(TypeId::of::<T>()).or_insert(value).clone();
+        Ok(value.downcast().expect("instance data type mismatch"))
+    }
+
+    fn set_instance_data<T>(&self, value: T) -> Result<Rc<T>>
+        where T: 'static
+    {
+        let data = get_instance_data(*self)?;
+        let value = Rc::new(value);
+        let previous = data.values
+            .borrow_mut()
+            .insert(TypeId::of::<T>(), value.clone());
+        // dropped after the borrow has ended
+        drop(previous);
+        Ok(value)
+    }
+
+    fn add_cleanup_hook<F>(&self, hook: F) -> Result<CleanupHook>
+        where F: FnOnce() + 'static
+    {
+        let hook: CleanupFn = Box::new(hook);
+        let state = Rc::new(HookState { hook: RefCell::new(Some(hook)) });
+        // the registered hook owns a reference to the state, which is released
+        // when it runs or is removed
+        let arg = Rc::into_raw(state.clone());
+        let status = unsafe {
+            napi_add_env_cleanup_hook(*self, Some(run_cleanup_hook), arg as *mut os::raw::c_void)
+        };
+        if status != napi_status::napi_ok {
+            unsafe { drop(Rc::from_raw(arg)) };
+        }
+        napi::napi_either(*self,
+                          status,
+                          CleanupHook {
+                              env: *self,
+                              state: state,
+                          })
+    }
+
+    fn add_async_cleanup_hook<F>(&self, hook: F) -> Result<AsyncCleanupHook>
+        where F: FnOnce(AsyncCleanup) + 'static
+    {
+        let hook: AsyncCleanupFn = Box::new(hook);
+        let state = Rc::new(AsyncHookState {
+                                handle: Cell::new(ptr::null_mut()),
+                                hook: RefCell::new(Some(hook)),
+                            });
+        let arg = Rc::into_raw(state.clone());
+        let mut handle = ptr::null_mut();
+        let status = unsafe {
+            napi_add_async_cleanup_hook(*self,
+                                        Some(run_async_cleanup_hook),
+                                        arg as *mut os::raw::c_void,
+                                        &mut handle)
+        };
+        if status != napi_status::napi_ok {
+            unsafe { drop(Rc::from_raw(arg)) };
+        }
+        state.handle.set(handle);
+        napi::napi_either(*self, status, AsyncCleanupHook { state: state })
+    }
+}
+
+type CleanupFn = Box<FnOnce()>;
+type AsyncCleanupFn = Box<FnOnce(AsyncCleanup)>;
+
+struct HookState {
+    // taken once the hook has run or has been removed
+    hook: RefCell<Option<CleanupFn>>,
+}
+
+/// A registered cleanup hook. Dropping the handle leaves the hook registered.
+pub struct CleanupHook {
+    env: NapiEnv,
+    state: Rc<HookState>,
+}
+
+impl CleanupHook {
+    // unregisters the hook without running it, does nothing if it already ran
+    pub fn remove(self) -> Result<()> {
+        if self.state.hook.borrow_mut().take().is_none() {
+            return Ok(());
+        }
+        let arg = &*self.state as *const HookState;
+        let status = unsafe {
+            napi_remove_env_cleanup_hook(self.env,
+                                         Some(run_cleanup_hook),
+                                         arg as *mut os::raw::c_void)
+        };
+        napi::napi_either(self.env, status, ())?;
+        unsafe { drop(Rc::from_raw(arg)) };
+        Ok(())
+    }
+}
+
+unsafe extern "C" fn run_cleanup_hook(arg: *mut os::raw::c_void) {
+    let state = Rc::from_raw(arg as *const HookState);
+    let hook = state.hook.borrow_mut().take();
+    if let Some(hook) = hook {
+        hook();
+    }
+}
+
+struct AsyncHookState {
+    handle: Cell<napi_async_cleanup_hook_handle>,
+    hook: RefCell<Option<AsyncCleanupFn>>,
+}
+
+/// A registered async cleanup hook. Dropping the handle leaves the hook
+/// registered.
+pub struct AsyncCleanupHook {
+    state: Rc<AsyncHookState>,
+}
+
+impl AsyncCleanupHook {
+    // unregisters the hook without running it, does nothing if it already ran
+    pub fn remove(self) -> Result<()> {
+        if self.state.hook.borrow_mut().take().is_none() {
+            return Ok(());
+        }
+        let status = unsafe { napi_remove_async_cleanup_hook(self.state.handle.get()) };
+        if status != napi_status::napi_ok {
+            return Err(NapiError::from(status));
+        }
+        unsafe { drop(Rc::from_raw(&*self.state as *const AsyncHookState)) };
+        Ok(())
+    }
+}
+
+/// Passed to async cleanup hooks, dropping it (on the main thread) lets the
+/// env continue shutting down.
+#[derive(Debug)]
+pub struct AsyncCleanup {
+    handle: napi_async_cleanup_hook_handle,
+}
+
+impl AsyncCleanup {
+    pub fn done(self) {}
+}
+
+impl Drop for AsyncCleanup {
+    fn drop(&mut self) {
+        unsafe { napi_remove_async_cleanup_hook(self.handle) };
+    }
+}
+
+unsafe extern "C" fn run_async_cleanup_hook(handle: napi_async_cleanup_hook_handle,
+                                            arg: *mut os::raw::c_void) {
+    let state = Rc::from_raw(arg as *const AsyncHookState);
+    let hook = state.hook.borrow_mut().take();
+    match hook {
+        Some(hook) => hook(AsyncCleanup { handle: handle }),
+        None => {
+            napi_remove_async_cleanup_hook(handle);
+        }
+    }
+}
+
+#[derive(Default)]
+struct InstanceData {
+    values: RefCell<HashMap<TypeId, Rc<Any>>>,
+}
+
+impl InstanceData {
+    fn get<T: 'static>(&self) -> Option<Rc<T>> {
+        self.values
+            .borrow()
+            .get(&TypeId::of::<T>())
+            .map(|value| value.clone().downcast().expect("instance data type mismatch"))
+    }
+}
+
+// the instance data is only ever accessed from the env's main thread and lives
+// until the env shuts down
+fn get_instance_data<'a>(env: NapiEnv) -> Result<&'a InstanceData> {
+    let mut data = ptr::null_mut();
+    let status = unsafe { napi_get_instance_data(env, &mut data) };
+    napi::napi_either(env, status, ())?;
+    if !data.is_null() {
+        return Ok(unsafe { &*(data as *const InstanceData) });
+    }
+
+    let data = Box::into_raw(Box::new(InstanceData::default()));
+    let status = unsafe {
+        napi_set_instance_data(env,
+                               data as *mut os::raw::c_void,
+                               Some(finalize_instance_data),
+                               ptr::null_mut())
+    };
+    if status != napi_status::napi_ok {
+        unsafe { drop(Box::from_raw(data)) };
+    }
+    napi::napi_either(env, status, unsafe { &*data })
+}
+
+unsafe extern "C" fn finalize_instance_data(_env: NapiEnv,
+                                            finalize_data: *mut os::raw::c_void,
+                                            _finalize_hint: *mut os::raw::c_void) {
+    let data = Box::from_raw(finalize_data as *mut InstanceData);
+    // values may access other instance data while being dropped, so they are
+    // dropped one by one outside of any borrow
+    loop {
+        let key = match data.values.borrow().keys().next() {
+            Some(key) => *key,
+            None => break,
+        };
+        let value = data.values.borrow_mut().remove(&key);
+        drop(value);
+    }
+}
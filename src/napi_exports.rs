@@ -0,0 +1,120 @@
+use std::any::Any;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::result::Result as StdResult;
+
+use napi::{self, NapiEnv, NapiValue};
+use napi_value::{FromNapiValues, IntoNapiValue, IntoReturnValue};
+use napi_property::{self, PropertyDescriptor};
+use napi_class::{self, Class};
+use error::{Result, NapiError, NapiErrorType};
+
+/// The `exports` object of a module being initialized by `napi_module!`.
+#[derive(Debug)]
+pub struct Exports {
+    env: NapiEnv,
+    exports: NapiValue,
+    module: NapiValue,
+}
+
+impl Exports {
+    pub fn env(&self) -> NapiEnv {
+        self.env
+    }
+
+    pub fn object(&self) -> NapiValue {
+        self.exports
+    }
+
+    pub fn module(&self) -> NapiValue {
+        self.module
+    }
+
+    pub fn function<F, T, R, M>(&mut self, name: &str, f: F) -> Result<&mut Self>
+        where F: Fn(NapiEnv, NapiValue, T) -> R + 'static,
+              T: FromNapiValues,
+              R: IntoReturnValue<M>
+    {
+        let function = napi::create_function(self.env, name, f)?;
+        self.set(name, function)
+    }
+
+    pub fn function_mut<F, T, R, M>(&mut self, name: &str, f: F) -> Result<&mut Self>
+        where F: FnMut(NapiEnv, NapiValue, T) -> R + 'static,
+              T: FromNapiValues,
+              R: IntoReturnValue<M>
+    {
+        let function = napi::create_function_mut(self.env, name, f)?;
+        self.set(name, function)
+    }
+
+    pub fn async_function<F, T, Fut, R, E>(&mut self, name: &str, f: F) -> Result<&mut Self>
+        where F: Fn(NapiEnv, NapiValue, T) -> Fut + 'static,
+              T: FromNapiValues,
+              Fut: Future<Output = StdResult<R, E>> + Send + 'static,
+              R: IntoNapiValue + Send + 'static,
+              E: IntoNapiValue + Send + 'static
+    {
+        let function = napi::create_async_function(self.env, name, f)?;
+        self.set(name, function)
+    }
+
+    pub fn value<T>(&mut self, name: &str, value: T) -> Result<&mut Self>
+        where T: IntoNapiValue
+    {
+        let value = value.into_napi_value(self.env)?;
+        self.set(name, value)
+    }
+
+    // exports the class under `T::NAME`
+    pub fn class<T>(&mut self) -> Result<&mut Self>
+        where T: Class
+    {
+        let constructor = napi_class::define_class::<T>(self.env)?;
+        self.set(T::NAME, constructor)
+    }
+
+    pub fn property(&mut self, property: PropertyDescriptor) -> Result<&mut Self> {
+        self.properties(vec![property])
+    }
+
+    pub fn properties(&mut self, properties: Vec<PropertyDescriptor>) -> Result<&mut Self> {
+        napi_property::define_properties(self.env, self.exports, properties)?;
+        Ok(self)
+    }
+
+    fn set(&mut self, name: &str, value: NapiValue) -> Result<&mut Self> {
+        napi::set_named_property(self.env, self.exports, name, value)?;
+        Ok(self)
+    }
+}
+
+// runs the init closure of a module, throwing errors and panics to the caller
+// of `require()`. Called from the register function generated by `napi_module!`.
+pub fn init_module<F>(env: NapiEnv, exports: NapiValue, module: NapiValue, init: F)
+    where F: FnOnce(&mut Exports) -> Result<()>
+{
+    let mut builder = Exports {
+        env: env,
+        exports: exports,
+        module: module,
+    };
+    let result = panic::catch_unwind(AssertUnwindSafe(|| init(&mut builder)))
+        .unwrap_or_else(|panic| Err(panic_error(panic)));
+    if let Err(err) = result {
+        let _ = err.into_napi_value(env).and_then(|error| napi::throw(env, error));
+    }
+}
+
+fn panic_error(panic: Box<Any + Send>) -> NapiError {
+    let message = panic
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string());
+    NapiError {
+        error_message: format!("module initialization panicked: {}", message),
+        engine_error_code: 0,
+        error_code: NapiErrorType::GenericFailure,
+    }
+}
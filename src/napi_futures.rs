@@ -1,28 +1,141 @@
-use std::boxed::Box;
+use std::cell::RefCell;
+use std::result::Result as StdResult;
 
-use napi::{NapiEnv, NapiValue, call_function};
-use napi_value::{IntoNapiValue, FromNapiValues};
+use futures::{Future, FutureExt};
+use futures::executor::ThreadPool;
+#[cfg(feature = "tokio")]
+use tokio::runtime::{self, Runtime};
+#[cfg(feature = "tokio")]
+use std::sync::Arc;
+#[cfg(feature = "tokio")]
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use napi::{NapiEnv, NapiValue};
+use napi_env::NapiEnvExt;
+use napi_value::IntoNapiValue;
+use napi_promise::{Deferred, Promise};
 use error::Result;
 
-pub struct ThenArgs<T, E> {
-    pub on_fulfilled: Box<Fn(NapiEnv, NapiValue, T) + Send>,
-    pub on_rejected: Box<Fn(NapiEnv, NapiValue, E) + Send>,
+const THREAD_NAME_PREFIX: &'static str = "node-api-executor-";
+
+// the executor of an env, dropped along with the env's instance data
+#[derive(Default)]
+struct EnvExecutor(RefCell<Option<Executor>>);
+
+/// Polls futures on a pool of worker threads and settles the corresponding
+/// promises back on the main JS thread, so the event loop is never blocked.
+#[derive(Clone)]
+pub struct Executor {
+    inner: Inner,
+}
+
+#[derive(Clone)]
+enum Inner {
+    ThreadPool(ThreadPool),
+    #[cfg(feature = "tokio")]
+    Tokio(Arc<Runtime>),
+}
+
+impl Executor {
+    pub fn new(pool_size: usize) -> Executor {
+        let pool = ThreadPool::builder()
+            .pool_size(pool_size)
+            .name_prefix(THREAD_NAME_PREFIX)
+            .create()
+            .expect("error creating executor thread pool");
+        Executor { inner: Inner::ThreadPool(pool) }
+    }
+
+    #[cfg(feature = "tokio")]
+    pub fn tokio(runtime: Arc<Runtime>) -> Executor {
+        Executor { inner: Inner::Tokio(runtime) }
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    fn default_executor() -> Executor {
+        let pool = ThreadPool::builder()
+            .name_prefix(THREAD_NAME_PREFIX)
+            .create()
+            .expect("error creating executor thread pool");
+        Executor { inner: Inner::ThreadPool(pool) }
+    }
+
+    // workers are numbered like those of the thread pool
+    #[cfg(feature = "tokio")]
+    fn default_executor() -> Executor {
+        static WORKER_ID: AtomicUsize = AtomicUsize::new(0);
+        let runtime = runtime::Builder::new_multi_thread()
+            .thread_name_fn(|| {
+                                format!("{}{}",
+                                        THREAD_NAME_PREFIX,
+                                        WORKER_ID.fetch_add(1, Ordering::SeqCst))
+                            })
+            .enable_all()
+            .build()
+            .expect("error creating tokio runtime");
+        Executor::tokio(Arc::new(runtime))
+    }
+
+    // returns the executor of the env, creating one with a worker per cpu if
+    // there is none yet
+    pub fn current(env: NapiEnv) -> Result<Executor> {
+        let executor = env.instance_data::<EnvExecutor>()?;
+        let mut executor = executor.0.borrow_mut();
+        Ok(executor
+               .get_or_insert_with(Executor::default_executor)
+               .clone())
+    }
+
+    // replaces the executor used for the env, e.g. to change the pool size
+    pub fn install(self, env: NapiEnv) -> Result<()> {
+        *env.instance_data::<EnvExecutor>()?.0.borrow_mut() = Some(self);
+        Ok(())
+    }
+
+    pub fn spawn_promise<F, T, E>(&self, env: NapiEnv, future: F) -> Result<Promise>
+        where F: Future<Output = StdResult<T, E>> + Send + 'static,
+              T: IntoNapiValue + Send + 'static,
+              E: IntoNapiValue + Send + 'static
+    {
+        let (deferred, promise) = Deferred::new(env)?;
+        let deferred = deferred.into_threadsafe()?;
+        let settle = future.map(move |result| {
+            let _ = match result {
+                Ok(value) => deferred.resolve(value),
+                Err(err) => deferred.reject(err),
+            };
+        });
+        match self.inner {
+            Inner::ThreadPool(ref pool) => pool.spawn_ok(settle),
+            #[cfg(feature = "tokio")]
+            Inner::Tokio(ref runtime) => {
+                runtime.spawn(settle);
+            }
+        }
+        Ok(promise)
+    }
+}
+
+pub fn spawn_promise<F, T, E>(env: NapiEnv, future: F) -> Result<Promise>
+    where F: Future<Output = StdResult<T, E>> + Send + 'static,
+          T: IntoNapiValue + Send + 'static,
+          E: IntoNapiValue + Send + 'static
+{
+    Executor::current(env)?.spawn_promise(env, future)
 }
 
-#[allow(unused_variables, unused_must_use)]
-impl<T, E> FromNapiValues for ThenArgs<T, E>
-    where T: IntoNapiValue
+/// Wraps a future so that converting it spawns it on the env's executor and
+/// hands a `Promise` for its result to JS. Functions can return futures as
+/// they are; this is for values that must be `IntoNapiValue`, e.g. those of
+/// getters or a future inside a `Result`.
+pub struct Async<F>(pub F);
+
+impl<F, T, E> IntoNapiValue for Async<F>
+    where F: Future<Output = StdResult<T, E>> + Send + 'static,
+          T: IntoNapiValue + Send + 'static,
+          E: IntoNapiValue + Send + 'static
 {
-    fn from_napi_values(env: NapiEnv, this: NapiValue, values: &[NapiValue]) -> Result<Self> {
-        let fulfilled_function = values[0].clone();
-        Ok(ThenArgs {
-               on_fulfilled: Box::new(move |env, this, args| {
-                                          let napi_args = [args.into_napi_value(env).unwrap()];
-                                          call_function(env, this, fulfilled_function, &napi_args);
-                                      }),
-               on_rejected: Box::new(|_, _, _| {
-                                         println!("onrejected called");
-                                     }),
-           })
+    fn into_napi_value(self, env: NapiEnv) -> Result<NapiValue> {
+        spawn_promise(env, self.0)?.into_napi_value(env)
     }
 }
@@ -0,0 +1,357 @@
+use std::any::Any;
+use std::ops::Deref;
+use std::slice;
+
+use napi::{self, NapiEnv, NapiValue, NapiValueType, TypedArrayType};
+use napi_value::{FromNapiValue, FromNapiValues, IntoNapiValue, IntoNapiValues,
+                 IntoReturnValue};
+use napi_js_value::JsValue;
+use napi_promise::{Deferred, Promise};
+use error::Result;
+
+/// Implemented by the typed wrappers around `JsValue`, which are obtained
+/// through `JsValue::downcast` or by converting function arguments.
+pub trait JsType: Sized {
+    // used in error messages
+    const NAME: &'static str;
+
+    fn is_type(value: &JsValue) -> Result<bool>;
+
+    // the caller has to make sure `value` is of the right type
+    fn from_value_unchecked(value: JsValue) -> Self;
+}
+
+macro_rules! js_type {
+    ($(#[$attr:meta])* $name:ident, $type_name:expr, |$value:ident| $is_type:expr) => {
+        $(#[$attr])*
+        #[derive(Debug, Clone, Copy, PartialEq)]
+        pub struct $name(JsValue);
+
+        impl JsType for $name {
+            const NAME: &'static str = $type_name;
+
+            fn is_type($value: &JsValue) -> Result<bool> {
+                $is_type
+            }
+
+            fn from_value_unchecked(value: JsValue) -> Self {
+                $name(value)
+            }
+        }
+
+        impl Deref for $name {
+            type Target = JsValue;
+
+            fn deref(&self) -> &JsValue {
+                &self.0
+            }
+        }
+
+        impl From<$name> for JsValue {
+            fn from(value: $name) -> JsValue {
+                value.0
+            }
+        }
+
+        impl FromNapiValue for $name {
+            fn from_napi_value(env: NapiEnv, value: NapiValue) -> Result<Self> {
+                JsValue::new(env, value).downcast()
+            }
+        }
+
+        impl FromNapiValues for $name {
+            fn from_napi_values(env: NapiEnv, _: NapiValue, napi_values: &[NapiValue]) -> Result<Self> {
+                let undefined = napi::get_undefined(env)?;
+                $name::from_napi_value(env, *napi_values.get(0).unwrap_or(&undefined))
+            }
+        }
+
+        impl IntoNapiValue for $name {
+            fn into_napi_value(self, _: NapiEnv) -> Result<NapiValue> {
+                Ok(self.0.raw())
+            }
+        }
+    }
+}
+
+fn has_type(value: &JsValue, value_type: NapiValueType) -> Result<bool> {
+    Ok(value.type_of()? == value_type)
+}
+
+// newer engines report every `ArrayBufferView` as a buffer, so the type of the
+// typed array is checked as well
+fn is_byte_array(value: &JsValue) -> Result<bool> {
+    let (env, raw) = (value.env(), value.raw());
+    Ok(napi::is_buffer(env, raw)? && napi::is_typedarray(env, raw)? &&
+       napi::get_typedarray_info(env, raw)?.0 == TypedArrayType::Uint8)
+}
+
+js_type!(JsUndefined, "undefined", |value| has_type(value, NapiValueType::Undefined));
+js_type!(JsNull, "null", |value| has_type(value, NapiValueType::Null));
+js_type!(JsBoolean, "boolean", |value| has_type(value, NapiValueType::Boolean));
+js_type!(JsNumber, "number", |value| has_type(value, NapiValueType::Number));
+js_type!(JsString, "string", |value| has_type(value, NapiValueType::String));
+js_type!(JsSymbol, "symbol", |value| has_type(value, NapiValueType::Symbol));
+js_type!(JsFunction, "function", |value| has_type(value, NapiValueType::Function));
+js_type!(JsExternal, "external", |value| has_type(value, NapiValueType::External));
+js_type!(
+    /// Any object, including functions, arrays and class instances.
+    JsObject, "object", |value| {
+        let value_type = value.type_of()?;
+        Ok(value_type == NapiValueType::Object || value_type == NapiValueType::Function)
+    });
+js_type!(JsArray, "array", |value| napi::is_array(value.env(), value.raw()));
+js_type!(
+    /// A `Buffer` or any other `Uint8Array`.
+    JsBuffer, "Buffer", |value| is_byte_array(value));
+js_type!(JsTypedArray, "TypedArray", |value| napi::is_typedarray(value.env(), value.raw()));
+js_type!(JsPromise, "Promise", |value| napi::is_promise(value.env(), value.raw()));
+js_type!(JsError, "Error", |value| napi::is_error(value.env(), value.raw()));
+js_type!(JsDate, "Date", |value| napi::is_date(value.env(), value.raw()));
+
+impl JsUndefined {
+    pub fn new(env: NapiEnv) -> Result<JsUndefined> {
+        napi::get_undefined(env).map(|value| JsUndefined(JsValue::new(env, value)))
+    }
+}
+
+impl JsNull {
+    pub fn new(env: NapiEnv) -> Result<JsNull> {
+        napi::get_null(env).map(|value| JsNull(JsValue::new(env, value)))
+    }
+}
+
+impl JsBoolean {
+    pub fn new(env: NapiEnv, value: bool) -> Result<JsBoolean> {
+        napi::get_boolean(env, value).map(|value| JsBoolean(JsValue::new(env, value)))
+    }
+
+    pub fn value(&self) -> Result<bool> {
+        napi::get_value_bool(self.env(), self.raw())
+    }
+}
+
+impl JsNumber {
+    pub fn new(env: NapiEnv, value: f64) -> Result<JsNumber> {
+        napi::create_number(env, value).map(|value| JsNumber(JsValue::new(env, value)))
+    }
+
+    pub fn value(&self) -> Result<f64> {
+        napi::get_value_double(self.env(), self.raw())
+    }
+
+    pub fn value_i64(&self) -> Result<i64> {
+        napi::get_value_int64(self.env(), self.raw())
+    }
+}
+
+impl JsString {
+    pub fn new(env: NapiEnv, value: &str) -> Result<JsString> {
+        napi::create_string_utf8(env, value).map(|value| JsString(JsValue::new(env, value)))
+    }
+
+    pub fn value(&self) -> Result<String> {
+        napi::get_value_string_utf8(self.env(), self.raw())
+    }
+}
+
+impl JsSymbol {
+    pub fn new(env: NapiEnv, description: Option<&str>) -> Result<JsSymbol> {
+        JsValue::symbol(env, description).map(JsSymbol)
+    }
+
+    pub fn description(&self) -> Result<Option<String>> {
+        self.symbol_description()
+    }
+}
+
+impl JsFunction {
+    pub fn new<F, T, R, M>(env: NapiEnv, name: &str, f: F) -> Result<JsFunction>
+        where F: Fn(NapiEnv, NapiValue, T) -> R + 'static,
+              T: FromNapiValues,
+              R: IntoReturnValue<M>
+    {
+        napi::create_function(env, name, f).map(|value| JsFunction(JsValue::new(env, value)))
+    }
+
+    // see `napi::create_function_mut`
+    pub fn new_mut<F, T, R, M>(env: NapiEnv, name: &str, f: F) -> Result<JsFunction>
+        where F: FnMut(NapiEnv, NapiValue, T) -> R + 'static,
+              T: FromNapiValues,
+              R: IntoReturnValue<M>
+    {
+        napi::create_function_mut(env, name, f).map(|value| JsFunction(JsValue::new(env, value)))
+    }
+
+    // see `napi::create_function_with_data`
+    pub fn with_data<D, F, T, R, M>(env: NapiEnv, name: &str, data: D, f: F) -> Result<JsFunction>
+        where D: Any,
+              F: Fn(NapiEnv, NapiValue, T) -> R + 'static,
+              T: FromNapiValues,
+              R: IntoReturnValue<M>
+    {
+        napi::create_function_with_data(env, name, data, f)
+            .map(|value| JsFunction(JsValue::new(env, value)))
+    }
+
+    // calls the function with `this` as the receiver, e.g. `()` for
+    // `undefined`, passing each element of the `args` tuple as an argument
+    // `R` is the only type parameter, so that it can be given on its own as in
+    // `f.call::<bool>((), (1, 2))`
+    pub fn call<R>(&self, this: impl IntoNapiValue, args: impl IntoNapiValues) -> Result<R>
+        where R: FromNapiValue
+    {
+        let env = self.env();
+        let this = this.into_napi_value(env)?;
+        let args = args.into_napi_values(env)?;
+        let result = napi::call_function(env, this, self.raw(), &args)?;
+        R::from_napi_value(env, result)
+    }
+}
+
+impl JsObject {
+    pub fn new(env: NapiEnv) -> Result<JsObject> {
+        napi::create_object(env).map(|value| JsObject(JsValue::new(env, value)))
+    }
+
+    // the names of the object's own and inherited enumerable string properties
+    pub fn property_names(&self) -> Result<JsArray> {
+        napi::get_property_names(self.env(), self.raw())
+            .map(|value| JsArray(JsValue::new(self.env(), value)))
+    }
+}
+
+impl JsArray {
+    pub fn new(env: NapiEnv) -> Result<JsArray> {
+        napi::create_array(env).map(|value| JsArray(JsValue::new(env, value)))
+    }
+
+    pub fn with_length(env: NapiEnv, length: usize) -> Result<JsArray> {
+        napi::array_with_length(env, length).map(|value| JsArray(JsValue::new(env, value)))
+    }
+
+    pub fn len(&self) -> Result<usize> {
+        napi::get_array_length(self.env(), self.raw())
+    }
+
+    pub fn is_empty(&self) -> Result<bool> {
+        self.len().map(|length| length == 0)
+    }
+
+    pub fn get(&self, index: usize) -> Result<JsValue> {
+        napi::get_element(self.env(), self.raw(), index)
+            .map(|value| JsValue::new(self.env(), value))
+    }
+
+    pub fn set<V>(&self, index: usize, value: V) -> Result<()>
+        where V: IntoNapiValue
+    {
+        let value = value.into_napi_value(self.env())?;
+        napi::set_element(self.env(), self.raw(), index, value)
+    }
+}
+
+impl JsBuffer {
+    // a new `Buffer` holding a copy of `data`
+    pub fn new(env: NapiEnv, data: &[u8]) -> Result<JsBuffer> {
+        napi::create_buffer_copy(env, data).map(|value| JsBuffer(JsValue::new(env, value)))
+    }
+
+    pub fn len(&self) -> Result<usize> {
+        napi::get_buffer_info(self.env(), self.raw()).map(|(_, length)| length)
+    }
+
+    pub fn is_empty(&self) -> Result<bool> {
+        self.len().map(|length| length == 0)
+    }
+
+    // the buffer's contents, which stay valid for as long as the buffer does
+    pub fn data(&self) -> Result<&[u8]> {
+        let (data, length) = napi::get_buffer_info(self.env(), self.raw())?;
+        if length == 0 {
+            return Ok(&[]);
+        }
+        Ok(unsafe { slice::from_raw_parts(data, length) })
+    }
+}
+
+impl JsTypedArray {
+    pub fn array_type(&self) -> Result<TypedArrayType> {
+        napi::get_typedarray_info(self.env(), self.raw()).map(|(array_type, _)| array_type)
+    }
+
+    // the number of elements
+    pub fn len(&self) -> Result<usize> {
+        napi::get_typedarray_info(self.env(), self.raw()).map(|(_, length)| length)
+    }
+
+    pub fn is_empty(&self) -> Result<bool> {
+        self.len().map(|length| length == 0)
+    }
+
+    // the bytes the array views, in the platform's byte order, which stay
+    // valid for as long as the array does
+    pub fn data(&self) -> Result<&[u8]> {
+        let (data, length) = napi::get_typedarray_data(self.env(), self.raw())?;
+        if length == 0 {
+            return Ok(&[]);
+        }
+        Ok(unsafe { slice::from_raw_parts(data, length) })
+    }
+}
+
+impl JsExternal {
+    // `value` is dropped once the external has been garbage collected
+    pub fn new<T>(env: NapiEnv, value: T) -> Result<JsExternal>
+        where T: 'static
+    {
+        napi::create_external(env, Box::new(value))
+            .map(|value| JsExternal(JsValue::new(env, value)))
+    }
+
+    // externals carry no type, so the caller has to make sure that this one
+    // has been created with a `T`, e.g. through `JsExternal::new`
+    pub unsafe fn value<T>(&self) -> Result<&T> {
+        napi::get_external_data(self.env(), self.raw()).map(|data| &*(data as *const T))
+    }
+}
+
+impl JsPromise {
+    pub fn new(env: NapiEnv) -> Result<(Deferred, JsPromise)> {
+        let (deferred, promise) = Deferred::new(env)?;
+        Ok((deferred, JsPromise(JsValue::new(env, promise.value))))
+    }
+}
+
+impl From<JsPromise> for Promise {
+    fn from(promise: JsPromise) -> Promise {
+        Promise { value: promise.raw() }
+    }
+}
+
+impl JsDate {
+    // `time` is in milliseconds since the epoch
+    pub fn new(env: NapiEnv, time: f64) -> Result<JsDate> {
+        napi::create_date(env, time).map(|value| JsDate(JsValue::new(env, value)))
+    }
+
+    // NaN for invalid dates
+    pub fn time(&self) -> Result<f64> {
+        napi::get_date_value(self.env(), self.raw())
+    }
+
+    pub fn is_valid(&self) -> Result<bool> {
+        self.time().map(|time| !time.is_nan())
+    }
+}
+
+impl JsError {
+    pub fn new(env: NapiEnv, message: &str) -> Result<JsError> {
+        napi::create_error(env, message).map(|value| JsError(JsValue::new(env, value)))
+    }
+
+    pub fn message(&self) -> Result<String> {
+        let message = self.get_property("message")?;
+        napi::get_value_string_utf8(self.env(), message.raw())
+    }
+}
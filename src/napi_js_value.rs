@@ -0,0 +1,162 @@
+use napi::{self, NapiEnv, NapiValue, NapiValueType, WellKnownSymbol};
+use napi_value::{FromNapiValue, IntoNapiValue, IntoNapiValues};
+use napi_js_types::{JsType, JsFunction};
+use error::{Result, NapiError, NapiErrorType};
+
+/// A JS value of any type along with the env it belongs to. Converting from
+/// and into `JsValue` passes the value through as is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JsValue {
+    env: NapiEnv,
+    value: NapiValue,
+}
+
+impl JsValue {
+    pub fn new(env: NapiEnv, value: NapiValue) -> JsValue {
+        JsValue {
+            env: env,
+            value: value,
+        }
+    }
+
+    pub fn env(&self) -> NapiEnv {
+        self.env
+    }
+
+    pub fn raw(&self) -> NapiValue {
+        self.value
+    }
+
+    pub fn type_of(&self) -> Result<NapiValueType> {
+        napi::type_of(self.env, self.value)
+    }
+
+    pub fn is<T>(&self) -> Result<bool>
+        where T: JsType
+    {
+        T::is_type(self)
+    }
+
+    // fails with `NapiErrorType::InvalidArg` if the value is not a `T`
+    pub fn downcast<T>(&self) -> Result<T>
+        where T: JsType
+    {
+        if T::is_type(self)? {
+            Ok(T::from_value_unchecked(*self))
+        } else {
+            Err(NapiError {
+                    error_message: format!("expected {}, found {:?}", T::NAME, self.type_of()?),
+                    engine_error_code: 0,
+                    error_code: NapiErrorType::InvalidArg,
+                })
+        }
+    }
+
+    // the global object, e.g. to look up constructors like `Date` or `Map`
+    pub fn global(env: NapiEnv) -> Result<JsValue> {
+        napi::get_global(env).map(|value| JsValue::new(env, value))
+    }
+
+    pub fn symbol(env: NapiEnv, description: Option<&str>) -> Result<JsValue> {
+        napi::create_symbol(env, description).map(|value| JsValue::new(env, value))
+    }
+
+    pub fn well_known_symbol(env: NapiEnv, symbol: WellKnownSymbol) -> Result<JsValue> {
+        napi::get_well_known_symbol(env, symbol).map(|value| JsValue::new(env, value))
+    }
+
+    pub fn symbol_description(&self) -> Result<Option<String>> {
+        napi::get_symbol_description(self.env, self.value)
+    }
+
+    pub fn coerce_to_bool(&self) -> Result<JsValue> {
+        napi::coerce_to_bool(self.env, self.value).map(|value| JsValue::new(self.env, value))
+    }
+
+    pub fn coerce_to_number(&self) -> Result<JsValue> {
+        napi::coerce_to_number(self.env, self.value).map(|value| JsValue::new(self.env, value))
+    }
+
+    pub fn coerce_to_object(&self) -> Result<JsValue> {
+        napi::coerce_to_object(self.env, self.value).map(|value| JsValue::new(self.env, value))
+    }
+
+    pub fn coerce_to_string(&self) -> Result<JsValue> {
+        napi::coerce_to_string(self.env, self.value).map(|value| JsValue::new(self.env, value))
+    }
+
+    pub fn get_prototype(&self) -> Result<JsValue> {
+        napi::get_prototype(self.env, self.value).map(|value| JsValue::new(self.env, value))
+    }
+
+    pub fn instance_of(&self, constructor: &JsValue) -> Result<bool> {
+        napi::instanceof(self.env, self.value, constructor.value)
+    }
+
+    // `===` in JS
+    pub fn strict_equals(&self, other: &JsValue) -> Result<bool> {
+        napi::strict_equals(self.env, self.value, other.value)
+    }
+
+    // calls the value as a constructor with `new`, passing each element of the
+    // `args` tuple as an argument
+    pub fn construct<A>(&self, args: A) -> Result<JsValue>
+        where A: IntoNapiValues
+    {
+        let args = args.into_napi_values(self.env)?;
+        napi::new_instance(self.env, self.value, &args).map(|value| JsValue::new(self.env, value))
+    }
+
+    // calls the method `name` of the value, which fails if it is not a function
+    pub fn call_method<R>(&self, name: &str, args: impl IntoNapiValues) -> Result<R>
+        where R: FromNapiValue
+    {
+        self.get_property(name)?
+            .downcast::<JsFunction>()?
+            .call(*self, args)
+    }
+
+    // properties can be keyed by anything that converts into a JS value, e.g.
+    // strings, numbers or symbols
+    pub fn get_property<K>(&self, key: K) -> Result<JsValue>
+        where K: IntoNapiValue
+    {
+        let key = key.into_napi_value(self.env)?;
+        napi::get_property(self.env, self.value, key).map(|value| JsValue::new(self.env, value))
+    }
+
+    pub fn set_property<K, V>(&self, key: K, value: V) -> Result<()>
+        where K: IntoNapiValue,
+              V: IntoNapiValue
+    {
+        let key = key.into_napi_value(self.env)?;
+        let value = value.into_napi_value(self.env)?;
+        napi::set_property(self.env, self.value, key, value)
+    }
+
+    pub fn has_property<K>(&self, key: K) -> Result<bool>
+        where K: IntoNapiValue
+    {
+        let key = key.into_napi_value(self.env)?;
+        napi::has_property(self.env, self.value, key)
+    }
+
+    pub fn delete_property<K>(&self, key: K) -> Result<bool>
+        where K: IntoNapiValue
+    {
+        let key = key.into_napi_value(self.env)?;
+        napi::delete_property(self.env, self.value, key)
+    }
+}
+
+impl FromNapiValue for JsValue {
+    fn from_napi_value(env: NapiEnv, value: NapiValue) -> Result<Self> {
+        Ok(JsValue::new(env, value))
+    }
+}
+
+impl IntoNapiValue for JsValue {
+    fn into_napi_value(self, _: NapiEnv) -> Result<NapiValue> {
+        Ok(self.value)
+    }
+}
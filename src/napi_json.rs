@@ -0,0 +1,161 @@
+use serde_json::{Map, Number, Value};
+
+use napi::{self, NapiEnv, NapiValue, NapiValueType};
+use napi_value::{FromNapiValue, FromNapiValues, IntoNapiValue};
+use error::{Result, NapiError, NapiErrorType};
+
+// largest integer that JS numbers represent exactly
+const MAX_SAFE_INTEGER: f64 = 9007199254740991.0;
+
+impl IntoNapiValue for Value {
+    fn into_napi_value(self, env: NapiEnv) -> Result<NapiValue> {
+        match self {
+            Value::Null => napi::get_null(env),
+            Value::Bool(value) => napi::get_boolean(env, value),
+            Value::Number(number) => napi::create_number(env, number.as_f64().unwrap_or(0.0)),
+            Value::String(value) => napi::create_string_utf8_with_len(env, &value),
+            Value::Array(values) => {
+                let array = napi::array_with_length(env, values.len())?;
+                for (index, value) in values.into_iter().enumerate() {
+                    let value = value.into_napi_value(env)?;
+                    napi::set_element(env, array, index, value)?;
+                }
+                Ok(array)
+            }
+            Value::Object(map) => {
+                let object = napi::create_object(env)?;
+                for (key, value) in map {
+                    // keys may contain 0 bytes, so they are not passed as C strings
+                    let key = napi::create_string_utf8_with_len(env, &key)?;
+                    let value = value.into_napi_value(env)?;
+                    napi::set_property(env, object, key, value)?;
+                }
+                Ok(object)
+            }
+        }
+    }
+}
+
+// follows `JSON.stringify`: `toJSON` methods are called, `undefined`
+// properties are left out and non-finite numbers become `null`. Unlike it,
+// functions, symbols and cyclic objects are rejected instead of skipped.
+impl FromNapiValue for Value {
+    fn from_napi_value(env: NapiEnv, value: NapiValue) -> Result<Self> {
+        JsonConverter {
+                env: env,
+                ancestors: Vec::new(),
+                path: Vec::new(),
+            }
+            .convert(value)
+    }
+}
+
+impl FromNapiValues for Value {
+    fn from_napi_values(env: NapiEnv, _: NapiValue, napi_values: &[NapiValue]) -> Result<Self> {
+        let undefined = napi::get_undefined(env)?;
+        Value::from_napi_value(env, *napi_values.get(0).unwrap_or(&undefined))
+    }
+}
+
+struct JsonConverter {
+    env: NapiEnv,
+    // the objects currently being converted, to detect cycles
+    ancestors: Vec<NapiValue>,
+    // the property names and indices leading to the current value
+    path: Vec<String>,
+}
+
+impl JsonConverter {
+    fn convert(&mut self, value: NapiValue) -> Result<Value> {
+        self.convert_value(value, true)
+    }
+
+    // like `JSON.stringify`, `toJSON` is only called once per value
+    fn convert_value(&mut self, value: NapiValue, to_json: bool) -> Result<Value> {
+        match napi::type_of(self.env, value)? {
+            NapiValueType::Undefined | NapiValueType::Null => Ok(Value::Null),
+            NapiValueType::Boolean => napi::get_value_bool(self.env, value).map(Value::Bool),
+            NapiValueType::Number => napi::get_value_double(self.env, value).map(number),
+            NapiValueType::String => napi::get_value_string_utf8(self.env, value).map(Value::String),
+            NapiValueType::Object => self.convert_object(value, to_json),
+            value_type => Err(self.error(&format!("cannot convert a {:?} to JSON", value_type))),
+        }
+    }
+
+    fn convert_object(&mut self, object: NapiValue, to_json: bool) -> Result<Value> {
+        if to_json {
+            let to_json = napi::get_named_property(self.env, object, "toJSON")?;
+            if napi::type_of(self.env, to_json)? == NapiValueType::Function {
+                let value = napi::call_function(self.env, object, to_json, &[])?;
+                return self.convert_value(value, false);
+            }
+        }
+
+        for ancestor in &self.ancestors {
+            if napi::strict_equals(self.env, *ancestor, object)? {
+                return Err(self.error("cannot convert a cyclic object to JSON"));
+            }
+        }
+        self.ancestors.push(object);
+        let result = if napi::is_array(self.env, object)? {
+            self.convert_array(object)
+        } else {
+            self.convert_properties(object)
+        };
+        self.ancestors.pop();
+        result
+    }
+
+    fn convert_array(&mut self, array: NapiValue) -> Result<Value> {
+        let length = napi::get_array_length(self.env, array)?;
+        let mut values = Vec::with_capacity(length);
+        for index in 0..length {
+            let element = napi::get_element(self.env, array, index)?;
+            self.path.push(format!("[{}]", index));
+            let value = self.convert(element);
+            self.path.pop();
+            values.push(value?);
+        }
+        Ok(Value::Array(values))
+    }
+
+    fn convert_properties(&mut self, object: NapiValue) -> Result<Value> {
+        let names = napi::get_own_property_names(self.env, object)?;
+        let length = napi::get_array_length(self.env, names)?;
+        let mut map = Map::new();
+        for index in 0..length {
+            let name = napi::get_element(self.env, names, index)?;
+            let property = napi::get_property(self.env, object, name)?;
+            if napi::type_of(self.env, property)? == NapiValueType::Undefined {
+                continue;
+            }
+            let name = napi::get_value_string_utf8(self.env, name)?;
+            self.path.push(format!(".{}", name));
+            let value = self.convert(property);
+            self.path.pop();
+            map.insert(name, value?);
+        }
+        Ok(Value::Object(map))
+    }
+
+    fn error(&self, message: &str) -> NapiError {
+        NapiError {
+            error_message: format!("{} at ${}", message, self.path.concat()),
+            engine_error_code: 0,
+            error_code: NapiErrorType::InvalidArg,
+        }
+    }
+}
+
+// integers stay integers, so that they can be read back with `as_i64`
+fn number(value: f64) -> Value {
+    if value.trunc() == value && value.abs() <= MAX_SAFE_INTEGER {
+        if value >= 0.0 {
+            Value::Number(Number::from(value as u64))
+        } else {
+            Value::Number(Number::from(value as i64))
+        }
+    } else {
+        Number::from_f64(value).map(Value::Number).unwrap_or(Value::Null)
+    }
+}
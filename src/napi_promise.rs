@@ -0,0 +1,199 @@
+use std::boxed::Box;
+use std::{ptr, os};
+
+use node_api_sys::*;
+
+use napi::{self, NapiEnv, NapiValue, NapiDeferred};
+use napi_value::IntoNapiValue;
+use napi_async_context::AsyncContext;
+use error::{Result, NapiError};
+
+/// A JS `Promise` created from Rust, settled through its `Deferred`.
+#[derive(Debug, Clone, Copy)]
+pub struct Promise {
+    pub value: NapiValue,
+}
+
+impl IntoNapiValue for Promise {
+    fn into_napi_value(self, _: NapiEnv) -> Result<NapiValue> {
+        Ok(self.value)
+    }
+}
+
+/// The resolving end of a `Promise`. Must be settled on the main JS thread;
+/// use `into_threadsafe` to settle it from another thread.
+#[derive(Debug)]
+pub struct Deferred {
+    env: NapiEnv,
+    deferred: NapiDeferred,
+}
+
+impl Deferred {
+    pub fn new(env: NapiEnv) -> Result<(Deferred, Promise)> {
+        let (deferred, promise) = napi::create_promise(env)?;
+        Ok((Deferred {
+                env: env,
+                deferred: deferred,
+            },
+            Promise { value: promise }))
+    }
+
+    pub fn resolve<T>(self, value: T) -> Result<()>
+        where T: IntoNapiValue
+    {
+        match value.into_napi_value(self.env) {
+            Ok(value) => napi::resolve_deferred(self.env, self.deferred, value),
+            Err(err) => self.reject(err),
+        }
+    }
+
+    // if `err` cannot be converted, the promise is rejected with the error
+    // that kept it from converting instead of being left pending
+    pub fn reject<E>(self, err: E) -> Result<()>
+        where E: IntoNapiValue
+    {
+        let value = match err.into_napi_value(self.env) {
+            Ok(value) => value,
+            Err(conversion_err) => conversion_error(self.env, conversion_err)?,
+        };
+        napi::reject_deferred(self.env, self.deferred, value)
+    }
+
+    pub fn into_threadsafe(self) -> Result<ThreadsafeDeferred> {
+        let mut tsfn: napi_threadsafe_function = ptr::null_mut();
+        let name = napi::create_string_utf8(self.env, "node_api::ThreadsafeDeferred")?;
+        let async_context = AsyncContext::new(self.env, "node_api::ThreadsafeDeferred")?;
+        let context = Box::new(DeferredContext {
+                                   deferred: self.deferred,
+                                   async_context: async_context,
+                               });
+        let context = Box::into_raw(context) as *mut os::raw::c_void;
+        let status = unsafe {
+            napi_create_threadsafe_function(self.env,
+                                            0,
+                                            0,
+                                            name,
+                                            1,
+                                            1,
+                                            context,
+                                            Some(finalize_context),
+                                            context,
+                                            Some(settle_deferred),
+                                            &mut tsfn)
+        };
+        napi::napi_either(self.env, status, ThreadsafeDeferred { tsfn: tsfn })
+    }
+}
+
+// an exception thrown by JS during the conversion is used as is, any other
+// failure becomes an `Error`
+fn conversion_error(env: NapiEnv, err: NapiError) -> Result<NapiValue> {
+    if napi::is_exception_pending(env)? {
+        napi::get_and_clear_last_exception(env)
+    } else {
+        err.into_napi_value(env)
+    }
+}
+
+// only touched on the main thread, by `settle_deferred` and `finalize_context`
+struct DeferredContext {
+    deferred: NapiDeferred,
+    // the promise is settled in the async context it was made threadsafe in
+    async_context: AsyncContext,
+}
+
+type Settlement = Box<FnOnce(NapiEnv, NapiDeferred) -> Result<()> + Send>;
+
+/// A `Deferred` that can be sent to and settled from any thread. The
+/// settlement is queued onto the main JS thread's event loop.
+#[derive(Debug)]
+pub struct ThreadsafeDeferred {
+    tsfn: napi_threadsafe_function,
+}
+
+unsafe impl Send for ThreadsafeDeferred {}
+
+impl ThreadsafeDeferred {
+    pub fn resolve<T>(self, value: T) -> Result<()>
+        where T: IntoNapiValue + Send + 'static
+    {
+        self.settle(Box::new(move |env, deferred| {
+            Deferred {
+                    env: env,
+                    deferred: deferred,
+                }
+                .resolve(value)
+        }))
+    }
+
+    pub fn reject<E>(self, err: E) -> Result<()>
+        where E: IntoNapiValue + Send + 'static
+    {
+        self.settle(Box::new(move |env, deferred| {
+            Deferred {
+                    env: env,
+                    deferred: deferred,
+                }
+                .reject(err)
+        }))
+    }
+
+    fn settle(mut self, settlement: Settlement) -> Result<()> {
+        let tsfn = self.tsfn;
+        self.tsfn = ptr::null_mut();
+        let data = Box::into_raw(Box::new(settlement)) as *mut os::raw::c_void;
+        let status = unsafe {
+            napi_call_threadsafe_function(tsfn,
+                                          data,
+                                          napi_threadsafe_function_call_mode::napi_tsfn_blocking)
+        };
+        unsafe {
+            napi_release_threadsafe_function(tsfn,
+                                             napi_threadsafe_function_release_mode::napi_tsfn_release);
+        }
+        match status {
+            napi_status::napi_ok => Ok(()),
+            _ => {
+                // the settlement was never queued, so ownership stays with us
+                unsafe { drop(Box::from_raw(data as *mut Settlement)) };
+                Err(NapiError::from(status))
+            }
+        }
+    }
+}
+
+impl Drop for ThreadsafeDeferred {
+    fn drop(&mut self) {
+        if !self.tsfn.is_null() {
+            let tsfn = self.tsfn;
+            let _ = ThreadsafeDeferred { tsfn: tsfn }
+                .reject(NapiError::from(napi_status::napi_cancelled));
+        }
+    }
+}
+
+unsafe extern "C" fn settle_deferred(env: NapiEnv,
+                                     _js_callback: NapiValue,
+                                     context: *mut os::raw::c_void,
+                                     data: *mut os::raw::c_void) {
+    let settlement: Box<Settlement> = Box::from_raw(data as *mut Settlement);
+    // a null env means the environment is shutting down and the promise
+    // can no longer be settled
+    if env == 0 {
+        return;
+    }
+    let context = &*(context as *mut DeferredContext);
+    // panicking here would abort the process. The promise is settled even if
+    // its async context cannot be entered, as it would stay pending otherwise.
+    let scope = context.async_context.enter();
+    let settled = settlement(env, context.deferred);
+    if let Err(err) = scope.and(settled) {
+        napi::report_uncaught(env, err);
+    }
+}
+
+unsafe extern "C" fn finalize_context(_env: NapiEnv,
+                                      finalize_data: *mut os::raw::c_void,
+                                      _finalize_hint: *mut os::raw::c_void) {
+    drop(Box::from_raw(finalize_data as *mut DeferredContext));
+}
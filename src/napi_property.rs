@@ -0,0 +1,294 @@
+use std::boxed::Box;
+use std::ffi::CString;
+use std::rc::Rc;
+use std::{ptr, mem, os};
+
+use node_api_sys::*;
+
+use napi::{self, NapiEnv, NapiValue, NapiValueType};
+use napi_value::{FromNapiValue, FromNapiValues, IntoNapiValue, IntoReturnValue};
+use napi_call_context::CallContext;
+use error::{Result, NapiError, NapiErrorType};
+
+type Callback = Box<Fn(&CallContext) -> Result<NapiValue>>;
+type ValueThunk = Box<FnOnce(NapiEnv) -> Result<NapiValue>>;
+
+enum PropertyKind {
+    Value(ValueThunk),
+    Accessor {
+        getter: Option<Callback>,
+        setter: Option<Callback>,
+    },
+}
+
+/// Describes a property for `define_properties`. Properties are read-only,
+/// non-enumerable and non-configurable unless marked otherwise.
+pub struct PropertyDescriptor {
+    name: String,
+    kind: PropertyKind,
+    attributes: napi_property_attributes,
+}
+
+impl PropertyDescriptor {
+    fn new(name: &str, kind: PropertyKind) -> PropertyDescriptor {
+        PropertyDescriptor {
+            name: name.to_string(),
+            kind: kind,
+            attributes: napi_property_attributes_napi_default,
+        }
+    }
+
+    pub fn value<T>(name: &str, value: T) -> PropertyDescriptor
+        where T: IntoNapiValue + 'static
+    {
+        PropertyDescriptor::new(name,
+                                PropertyKind::Value(Box::new(move |env| {
+                                                                 value.into_napi_value(env)
+                                                             })))
+    }
+
+    // defines an existing JS value as is, without any conversion
+    pub fn raw_value(name: &str, value: NapiValue) -> PropertyDescriptor {
+        PropertyDescriptor::new(name, PropertyKind::Value(Box::new(move |_| Ok(value))))
+    }
+
+    // methods are functions of their own that own `f`, so they keep working
+    // once they have been taken off the object
+    pub fn method<F, T, R, M>(name: &str, f: F) -> PropertyDescriptor
+        where F: Fn(NapiEnv, NapiValue, T) -> R + 'static,
+              T: FromNapiValues,
+              R: IntoReturnValue<M>
+    {
+        let function_name = name.to_string();
+        PropertyDescriptor::new(name,
+                                PropertyKind::Value(Box::new(move |env| {
+                                                                 napi::create_function(env,
+                                                                                       &function_name,
+                                                                                       f)
+                                                             })))
+    }
+
+    pub fn getter<F, R>(name: &str, f: F) -> PropertyDescriptor
+        where F: Fn(NapiEnv, NapiValue) -> R + 'static,
+              R: IntoNapiValue
+    {
+        PropertyDescriptor::new(name,
+                                PropertyKind::Accessor {
+                                    getter: Some(Box::new(move |context| {
+                                                              f(context.env(), context.raw_this())
+                                                                  .into_napi_value(context.env())
+                                                          })),
+                                    setter: None,
+                                })
+    }
+
+    // adds a setter to an accessor property; properties that only have a
+    // setter read as `undefined`
+    pub fn setter<F, T>(self, f: F) -> PropertyDescriptor
+        where F: Fn(NapiEnv, NapiValue, T) + 'static,
+              T: FromNapiValue
+    {
+        self.fallible_setter(move |env, this, value| Ok(f(env, this, value)))
+    }
+
+    // like `setter`, with errors thrown to the assigning JS code
+    pub fn fallible_setter<F, T>(mut self, f: F) -> PropertyDescriptor
+        where F: Fn(NapiEnv, NapiValue, T) -> Result<()> + 'static,
+              T: FromNapiValue
+    {
+        let setter: Callback = Box::new(move |context| {
+            let env = context.env();
+            let undefined = napi::get_undefined(env)?;
+            let value = T::from_napi_value(env, *context.args().get(0).unwrap_or(&undefined))?;
+            f(env, context.raw_this(), value)?;
+            Ok(undefined)
+        });
+        self.kind = match self.kind {
+            PropertyKind::Accessor { getter, .. } => {
+                PropertyKind::Accessor {
+                    getter: getter,
+                    setter: Some(setter),
+                }
+            }
+            _ => {
+                PropertyKind::Accessor {
+                    getter: None,
+                    setter: Some(setter),
+                }
+            }
+        };
+        self
+    }
+
+    pub fn writable(mut self) -> PropertyDescriptor {
+        self.attributes |= napi_property_attributes_napi_writable;
+        self
+    }
+
+    pub fn enumerable(mut self) -> PropertyDescriptor {
+        self.attributes |= napi_property_attributes_napi_enumerable;
+        self
+    }
+
+    pub fn configurable(mut self) -> PropertyDescriptor {
+        self.attributes |= napi_property_attributes_napi_configurable;
+        self
+    }
+}
+
+// shared by the getter and the setter function of an accessor property and
+// dropped once both have been garbage collected
+struct PropertyCallbacks {
+    getter: Option<Callback>,
+    setter: Option<Callback>,
+}
+
+// the N-API descriptors for a set of properties, along with the names and
+// callbacks they point into
+struct RawDescriptors {
+    _names: Vec<CString>,
+    accessors: Vec<(String, Rc<PropertyCallbacks>)>,
+    descriptors: Vec<napi_property_descriptor>,
+}
+
+impl RawDescriptors {
+    fn new(env: NapiEnv, properties: Vec<PropertyDescriptor>) -> Result<RawDescriptors> {
+        let mut names = Vec::with_capacity(properties.len());
+        let mut accessors = Vec::new();
+        let mut descriptors = Vec::with_capacity(properties.len());
+
+        for property in properties {
+            let name = CString::new(property.name.as_str())?;
+            let mut descriptor = napi_property_descriptor {
+                utf8name: name.as_ptr(),
+                name: 0,
+                method: None,
+                getter: None,
+                setter: None,
+                value: 0,
+                attributes: property.attributes,
+                data: ptr::null_mut(),
+            };
+            names.push(name);
+
+            match property.kind {
+                PropertyKind::Value(value) => {
+                    descriptor.value = value(env)?;
+                }
+                PropertyKind::Accessor { getter, setter } => {
+                    if getter.is_some() {
+                        descriptor.getter = Some(call_getter);
+                    }
+                    if setter.is_some() {
+                        descriptor.setter = Some(call_setter);
+                    }
+                    let callbacks = Rc::new(PropertyCallbacks {
+                                                getter: getter,
+                                                setter: setter,
+                                            });
+                    descriptor.data = &*callbacks as *const PropertyCallbacks as
+                                      *mut os::raw::c_void;
+                    accessors.push((property.name, callbacks));
+                }
+            }
+            descriptors.push(descriptor);
+        }
+
+        Ok(RawDescriptors {
+               _names: names,
+               accessors: accessors,
+               descriptors: descriptors,
+           })
+    }
+
+    // hands the accessor callbacks over to the getter and setter functions
+    // that have been defined on `object`. Callbacks that cannot be handed over
+    // are leaked rather than freed while JS may still call them.
+    fn attach_callbacks(self, env: NapiEnv, object: NapiValue) -> Result<()> {
+        let mut result = Ok(());
+        for (name, callbacks) in self.accessors {
+            if result.is_ok() {
+                result = attach_accessor(env, object, &name, &callbacks);
+            }
+            if result.is_err() {
+                mem::forget(callbacks);
+            }
+        }
+        result
+    }
+}
+
+fn attach_accessor(env: NapiEnv,
+                   object: NapiValue,
+                   name: &str,
+                   callbacks: &Rc<PropertyCallbacks>)
+                   -> Result<()> {
+    let global = napi::get_global(env)?;
+    let object_constructor = napi::get_named_property(env, global, "Object")?;
+    let get_descriptor =
+        napi::get_named_property(env, object_constructor, "getOwnPropertyDescriptor")?;
+    let key = napi::create_string_utf8(env, name)?;
+    let descriptor = napi::call_function(env, object_constructor, get_descriptor, &[object, key])?;
+    if napi::type_of(env, descriptor)? != NapiValueType::Object {
+        return Err(accessor_not_found(name));
+    }
+    for &(key, defined) in &[("get", callbacks.getter.is_some()),
+                             ("set", callbacks.setter.is_some())] {
+        if !defined {
+            continue;
+        }
+        let function = napi::get_named_property(env, descriptor, key)?;
+        if napi::type_of(env, function)? != NapiValueType::Function {
+            return Err(accessor_not_found(name));
+        }
+        napi::add_finalizer(env, function, Box::new(callbacks.clone()))?;
+    }
+    Ok(())
+}
+
+fn accessor_not_found(name: &str) -> NapiError {
+    NapiError {
+        error_message: format!("accessor {} not found after defining it", name),
+        engine_error_code: 0,
+        error_code: NapiErrorType::GenericFailure,
+    }
+}
+
+pub fn define_properties(env: NapiEnv,
+                         object: NapiValue,
+                         properties: Vec<PropertyDescriptor>)
+                         -> Result<()> {
+    let raw = RawDescriptors::new(env, properties)?;
+    let status = unsafe {
+        napi_define_properties(env, object, raw.descriptors.len(), raw.descriptors.as_ptr())
+    };
+    napi::napi_either(env, status, ())?;
+    raw.attach_callbacks(env, object)
+}
+
+unsafe fn invoke<F>(env: NapiEnv, cbinfo: napi_callback_info, select: F) -> NapiValue
+    where F: Fn(&PropertyCallbacks) -> &Option<Callback>
+{
+    let result = CallContext::new(env, cbinfo).and_then(|context| {
+        let callbacks = &*(context.data() as *const PropertyCallbacks);
+        let callback = select(callbacks)
+            .as_ref()
+            .expect("property callback not defined");
+        callback(&context)
+    });
+    match result {
+        Ok(value) => value,
+        Err(err) => {
+            let _ = err.into_napi_value(env).and_then(|error| napi::throw(env, error));
+            0
+        }
+    }
+}
+
+unsafe extern "C" fn call_getter(env: NapiEnv, cbinfo: napi_callback_info) -> NapiValue {
+    invoke(env, cbinfo, |callbacks| &callbacks.getter)
+}
+
+unsafe extern "C" fn call_setter(env: NapiEnv, cbinfo: napi_callback_info) -> NapiValue {
+    invoke(env, cbinfo, |callbacks| &callbacks.setter)
+}
@@ -0,0 +1,113 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::ptr;
+use std::rc::Rc;
+
+use node_api_sys::*;
+
+use napi::{self, NapiEnv, NapiRef, NapiValue};
+use napi_env::NapiEnvExt;
+use error::Result;
+
+/// An owned N-API reference to a JS value, deleted when dropped. References
+/// that are still alive when the env shuts down are deleted by a cleanup hook,
+/// after which `value` returns `None` and dropping them does nothing.
+#[derive(Debug)]
+pub struct Reference {
+    env: NapiEnv,
+    id: u64,
+    // reset to null once the reference has been deleted on shutdown
+    raw: Rc<Cell<NapiRef>>,
+    tracker: Rc<ReferenceTracker>,
+}
+
+impl Reference {
+    // a reference with a count of 0 is weak and does not keep the value alive
+    pub fn new(env: NapiEnv, value: NapiValue, initial_refcount: u32) -> Result<Reference> {
+        let mut raw: NapiRef = ptr::null_mut();
+        let status = unsafe { napi_create_reference(env, value, initial_refcount, &mut raw) };
+        napi::napi_either(env, status, ())?;
+        Reference::from_raw(env, raw)
+    }
+
+    // takes ownership of `raw`, which is deleted along with the reference
+    pub fn from_raw(env: NapiEnv, raw: NapiRef) -> Result<Reference> {
+        let tracker = ReferenceTracker::current(env)?;
+        let id = tracker.next_id.get();
+        tracker.next_id.set(id + 1);
+        let raw = Rc::new(Cell::new(raw));
+        tracker.refs.borrow_mut().insert(id, raw.clone());
+        Ok(Reference {
+               env: env,
+               id: id,
+               raw: raw,
+               tracker: tracker,
+           })
+    }
+
+    // returns `None` if the value has been garbage collected or the env has
+    // shut down
+    pub fn value(&self) -> Result<Option<NapiValue>> {
+        let raw = self.raw.get();
+        if raw.is_null() {
+            return Ok(None);
+        }
+        let mut value: NapiValue = 0;
+        let status = unsafe { napi_get_reference_value(self.env, raw, &mut value) };
+        napi::napi_either(self.env, status, if value == 0 { None } else { Some(value) })
+    }
+
+    pub fn ref_(&self) -> Result<u32> {
+        let mut count = 0;
+        let status = unsafe { napi_reference_ref(self.env, self.raw.get(), &mut count) };
+        napi::napi_either(self.env, status, count)
+    }
+
+    pub fn unref(&self) -> Result<u32> {
+        let mut count = 0;
+        let status = unsafe { napi_reference_unref(self.env, self.raw.get(), &mut count) };
+        napi::napi_either(self.env, status, count)
+    }
+
+    pub fn is_released(&self) -> bool {
+        self.raw.get().is_null()
+    }
+}
+
+impl Drop for Reference {
+    fn drop(&mut self) {
+        let raw = self.raw.replace(ptr::null_mut());
+        if !raw.is_null() {
+            self.tracker.refs.borrow_mut().remove(&self.id);
+            unsafe { napi_delete_reference(self.env, raw) };
+        }
+    }
+}
+
+// the references of an env that have not been dropped yet
+#[derive(Debug, Default)]
+struct ReferenceTracker {
+    refs: RefCell<HashMap<u64, Rc<Cell<NapiRef>>>>,
+    next_id: Cell<u64>,
+    hooked: Cell<bool>,
+}
+
+impl ReferenceTracker {
+    fn current(env: NapiEnv) -> Result<Rc<ReferenceTracker>> {
+        let tracker = env.instance_data::<ReferenceTracker>()?;
+        if !tracker.hooked.get() {
+            let hook_tracker = tracker.clone();
+            env.add_cleanup_hook(move || hook_tracker.release_all(env))?;
+            tracker.hooked.set(true);
+        }
+        Ok(tracker)
+    }
+
+    fn release_all(&self, env: NapiEnv) {
+        let refs: Vec<_> = self.refs.borrow_mut().drain().collect();
+        for (_, raw) in refs {
+            let raw = raw.replace(ptr::null_mut());
+            unsafe { napi_delete_reference(env, raw) };
+        }
+    }
+}
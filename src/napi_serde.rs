@@ -0,0 +1,784 @@
+use std::fmt::Display;
+
+use serde::{ser, de};
+use serde::ser::Serialize;
+use serde::de::{DeserializeOwned, IntoDeserializer};
+
+use napi::{self, NapiEnv, NapiValue, NapiValueType};
+use napi_value::{FromNapiValue, FromNapiValues, IntoNapiValue};
+use napi_js_value::JsValue;
+use napi_js_types::JsBuffer;
+use error::{Result, NapiError, NapiErrorType};
+
+// serializes `value` into a new JS value: structs and maps become objects,
+// sequences and tuples arrays, `None` and `()` `null`, and bytes `Buffer`s.
+// Enums are represented like serde_json does, e.g. `"Variant"` or
+// `{"Variant": value}`.
+pub fn to_js<T>(env: NapiEnv, value: &T) -> Result<NapiValue>
+    where T: Serialize + ?Sized
+{
+    value.serialize(Serializer { env: env })
+}
+
+// deserializes a `T` from `value`, the inverse of `to_js`. Functions, symbols
+// and other values that have no serde equivalent are rejected.
+pub fn from_js<T>(env: NapiEnv, value: NapiValue) -> Result<T>
+    where T: DeserializeOwned
+{
+    T::deserialize(Deserializer {
+                       env: env,
+                       value: value,
+                   })
+}
+
+/// Converts a serde type to and from JS values with `to_js` and `from_js`,
+/// e.g. to receive or return it from a function.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Serde<T>(pub T);
+
+impl<T> IntoNapiValue for Serde<T>
+    where T: Serialize
+{
+    fn into_napi_value(self, env: NapiEnv) -> Result<NapiValue> {
+        to_js(env, &self.0)
+    }
+}
+
+impl<T> FromNapiValue for Serde<T>
+    where T: DeserializeOwned
+{
+    fn from_napi_value(env: NapiEnv, value: NapiValue) -> Result<Self> {
+        from_js(env, value).map(Serde)
+    }
+}
+
+impl<T> FromNapiValues for Serde<T>
+    where T: DeserializeOwned
+{
+    fn from_napi_values(env: NapiEnv, _: NapiValue, napi_values: &[NapiValue]) -> Result<Self> {
+        let undefined = napi::get_undefined(env)?;
+        Serde::from_napi_value(env, *napi_values.get(0).unwrap_or(&undefined))
+    }
+}
+
+impl ser::Error for NapiError {
+    fn custom<T: Display>(msg: T) -> Self {
+        NapiError {
+            error_message: msg.to_string(),
+            engine_error_code: 0,
+            error_code: NapiErrorType::GenericFailure,
+        }
+    }
+}
+
+impl de::Error for NapiError {
+    fn custom<T: Display>(msg: T) -> Self {
+        NapiError {
+            error_message: msg.to_string(),
+            engine_error_code: 0,
+            error_code: NapiErrorType::InvalidArg,
+        }
+    }
+}
+
+struct Serializer {
+    env: NapiEnv,
+}
+
+impl ser::Serializer for Serializer {
+    type Ok = NapiValue;
+    type Error = NapiError;
+
+    type SerializeSeq = ArraySerializer;
+    type SerializeTuple = ArraySerializer;
+    type SerializeTupleStruct = ArraySerializer;
+    type SerializeTupleVariant = VariantSerializer<ArraySerializer>;
+    type SerializeMap = MapSerializer;
+    type SerializeStruct = ObjectSerializer;
+    type SerializeStructVariant = VariantSerializer<ObjectSerializer>;
+
+    fn serialize_bool(self, v: bool) -> Result<NapiValue> {
+        napi::get_boolean(self.env, v)
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<NapiValue> {
+        self.serialize_f64(v as f64)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<NapiValue> {
+        self.serialize_f64(v as f64)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<NapiValue> {
+        self.serialize_f64(v as f64)
+    }
+
+    // JS numbers can only represent integers up to 2^53 exactly
+    fn serialize_i64(self, v: i64) -> Result<NapiValue> {
+        self.serialize_f64(v as f64)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<NapiValue> {
+        self.serialize_f64(v as f64)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<NapiValue> {
+        self.serialize_f64(v as f64)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<NapiValue> {
+        self.serialize_f64(v as f64)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<NapiValue> {
+        self.serialize_f64(v as f64)
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<NapiValue> {
+        self.serialize_f64(v as f64)
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<NapiValue> {
+        napi::create_number(self.env, v)
+    }
+
+    fn serialize_char(self, v: char) -> Result<NapiValue> {
+        self.serialize_str(&v.to_string())
+    }
+
+    // also used for map keys, which may contain 0 bytes like any string
+    fn serialize_str(self, v: &str) -> Result<NapiValue> {
+        napi::create_string_utf8_with_len(self.env, v)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<NapiValue> {
+        napi::create_buffer_copy(self.env, v)
+    }
+
+    fn serialize_none(self) -> Result<NapiValue> {
+        napi::get_null(self.env)
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<NapiValue>
+        where T: Serialize + ?Sized
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<NapiValue> {
+        napi::get_null(self.env)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<NapiValue> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(self,
+                              _name: &'static str,
+                              _variant_index: u32,
+                              variant: &'static str)
+                              -> Result<NapiValue> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<NapiValue>
+        where T: Serialize + ?Sized
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(self,
+                                    _name: &'static str,
+                                    _variant_index: u32,
+                                    variant: &'static str,
+                                    value: &T)
+                                    -> Result<NapiValue>
+        where T: Serialize + ?Sized
+    {
+        let object = napi::create_object(self.env)?;
+        let value = to_js(self.env, value)?;
+        set_str_property(self.env, object, variant, value)?;
+        Ok(object)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<ArraySerializer> {
+        ArraySerializer::new(self.env, len)
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<ArraySerializer> {
+        ArraySerializer::new(self.env, Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<ArraySerializer> {
+        ArraySerializer::new(self.env, Some(len))
+    }
+
+    fn serialize_tuple_variant(self,
+                               _name: &'static str,
+                               _variant_index: u32,
+                               variant: &'static str,
+                               len: usize)
+                               -> Result<VariantSerializer<ArraySerializer>> {
+        VariantSerializer::new(self.env, variant, ArraySerializer::new(self.env, Some(len))?)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<MapSerializer> {
+        Ok(MapSerializer {
+               env: self.env,
+               object: napi::create_object(self.env)?,
+               key: None,
+           })
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<ObjectSerializer> {
+        ObjectSerializer::new(self.env)
+    }
+
+    fn serialize_struct_variant(self,
+                                _name: &'static str,
+                                _variant_index: u32,
+                                variant: &'static str,
+                                _len: usize)
+                                -> Result<VariantSerializer<ObjectSerializer>> {
+        VariantSerializer::new(self.env, variant, ObjectSerializer::new(self.env)?)
+    }
+}
+
+struct ArraySerializer {
+    env: NapiEnv,
+    array: NapiValue,
+    index: usize,
+}
+
+impl ArraySerializer {
+    fn new(env: NapiEnv, len: Option<usize>) -> Result<ArraySerializer> {
+        let array = match len {
+            Some(len) => napi::array_with_length(env, len)?,
+            None => napi::create_array(env)?,
+        };
+        Ok(ArraySerializer {
+               env: env,
+               array: array,
+               index: 0,
+           })
+    }
+
+    fn push<T>(&mut self, value: &T) -> Result<()>
+        where T: Serialize + ?Sized
+    {
+        let value = to_js(self.env, value)?;
+        napi::set_element(self.env, self.array, self.index, value)?;
+        self.index += 1;
+        Ok(())
+    }
+}
+
+impl ser::SerializeSeq for ArraySerializer {
+    type Ok = NapiValue;
+    type Error = NapiError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+        where T: Serialize + ?Sized
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<NapiValue> {
+        Ok(self.array)
+    }
+}
+
+impl ser::SerializeTuple for ArraySerializer {
+    type Ok = NapiValue;
+    type Error = NapiError;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+        where T: Serialize + ?Sized
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<NapiValue> {
+        Ok(self.array)
+    }
+}
+
+impl ser::SerializeTupleStruct for ArraySerializer {
+    type Ok = NapiValue;
+    type Error = NapiError;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+        where T: Serialize + ?Sized
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<NapiValue> {
+        Ok(self.array)
+    }
+}
+
+// field and variant names can be renamed to anything, including strings with
+// 0 bytes, so they are not passed as C strings
+fn set_str_property(env: NapiEnv, object: NapiValue, key: &str, value: NapiValue) -> Result<()> {
+    let key = napi::create_string_utf8_with_len(env, key)?;
+    napi::set_property(env, object, key, value)
+}
+
+struct ObjectSerializer {
+    env: NapiEnv,
+    object: NapiValue,
+}
+
+impl ObjectSerializer {
+    fn new(env: NapiEnv) -> Result<ObjectSerializer> {
+        Ok(ObjectSerializer {
+               env: env,
+               object: napi::create_object(env)?,
+           })
+    }
+}
+
+impl ser::SerializeStruct for ObjectSerializer {
+    type Ok = NapiValue;
+    type Error = NapiError;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+        where T: Serialize + ?Sized
+    {
+        let value = to_js(self.env, value)?;
+        set_str_property(self.env, self.object, key, value)
+    }
+
+    fn end(self) -> Result<NapiValue> {
+        Ok(self.object)
+    }
+}
+
+struct MapSerializer {
+    env: NapiEnv,
+    object: NapiValue,
+    key: Option<NapiValue>,
+}
+
+impl ser::SerializeMap for MapSerializer {
+    type Ok = NapiValue;
+    type Error = NapiError;
+
+    // keys become property names, so only strings and numbers are accepted
+    fn serialize_key<T>(&mut self, key: &T) -> Result<()>
+        where T: Serialize + ?Sized
+    {
+        let key = to_js(self.env, key)?;
+        match napi::type_of(self.env, key)? {
+            NapiValueType::String | NapiValueType::Number => {
+                self.key = Some(key);
+                Ok(())
+            }
+            key_type => {
+                Err(ser::Error::custom(format!("map keys must be strings or numbers, found {:?}",
+                                               key_type)))
+            }
+        }
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<()>
+        where T: Serialize + ?Sized
+    {
+        let key = self.key
+            .take()
+            .expect("serialize_value called before serialize_key");
+        let value = to_js(self.env, value)?;
+        napi::set_property(self.env, self.object, key, value)
+    }
+
+    fn end(self) -> Result<NapiValue> {
+        Ok(self.object)
+    }
+}
+
+// wraps the serialized variant into `{variant: value}`
+struct VariantSerializer<S> {
+    env: NapiEnv,
+    variant: &'static str,
+    inner: S,
+}
+
+impl<S> VariantSerializer<S> {
+    fn new(env: NapiEnv, variant: &'static str, inner: S) -> Result<VariantSerializer<S>> {
+        Ok(VariantSerializer {
+               env: env,
+               variant: variant,
+               inner: inner,
+           })
+    }
+
+    fn wrap(env: NapiEnv, variant: &'static str, value: NapiValue) -> Result<NapiValue> {
+        let object = napi::create_object(env)?;
+        set_str_property(env, object, variant, value)?;
+        Ok(object)
+    }
+}
+
+impl ser::SerializeTupleVariant for VariantSerializer<ArraySerializer> {
+    type Ok = NapiValue;
+    type Error = NapiError;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+        where T: Serialize + ?Sized
+    {
+        self.inner.push(value)
+    }
+
+    fn end(self) -> Result<NapiValue> {
+        VariantSerializer::<ArraySerializer>::wrap(self.env, self.variant, self.inner.array)
+    }
+}
+
+impl ser::SerializeStructVariant for VariantSerializer<ObjectSerializer> {
+    type Ok = NapiValue;
+    type Error = NapiError;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+        where T: Serialize + ?Sized
+    {
+        ser::SerializeStruct::serialize_field(&mut self.inner, key, value)
+    }
+
+    fn end(self) -> Result<NapiValue> {
+        VariantSerializer::<ObjectSerializer>::wrap(self.env, self.variant, self.inner.object)
+    }
+}
+
+struct Deserializer {
+    env: NapiEnv,
+    value: NapiValue,
+}
+
+impl Deserializer {
+    fn new(env: NapiEnv, value: NapiValue) -> Deserializer {
+        Deserializer {
+            env: env,
+            value: value,
+        }
+    }
+
+    fn invalid_type(&self, value_type: NapiValueType) -> NapiError {
+        de::Error::custom(format!("cannot deserialize a value of type {:?}", value_type))
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Deserializer {
+    type Error = NapiError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        match napi::type_of(self.env, self.value)? {
+            NapiValueType::Undefined | NapiValueType::Null => visitor.visit_unit(),
+            NapiValueType::Boolean => visitor.visit_bool(napi::get_value_bool(self.env, self.value)?),
+            NapiValueType::Number => {
+                let number = napi::get_value_double(self.env, self.value)?;
+                // integers are visited as such, so that they deserialize into
+                // integer types
+                if number.trunc() == number && number.abs() < 9007199254740992.0 {
+                    if number >= 0.0 {
+                        visitor.visit_u64(number as u64)
+                    } else {
+                        visitor.visit_i64(number as i64)
+                    }
+                } else {
+                    visitor.visit_f64(number)
+                }
+            }
+            NapiValueType::String => {
+                visitor.visit_string(napi::get_value_string_utf8(self.env, self.value)?)
+            }
+            NapiValueType::Object => {
+                if napi::is_array(self.env, self.value)? {
+                    let length = napi::get_array_length(self.env, self.value)?;
+                    visitor.visit_seq(ArrayAccess {
+                                          env: self.env,
+                                          array: self.value,
+                                          index: 0,
+                                          length: length,
+                                      })
+                } else if JsValue::new(self.env, self.value).is::<JsBuffer>()? {
+                    visitor.visit_byte_buf(buffer_data(self.env, self.value)?)
+                } else if napi::is_typedarray(self.env, self.value)? {
+                    // other typed arrays are sequences of numbers
+                    let (_, length) = napi::get_typedarray_info(self.env, self.value)?;
+                    visitor.visit_seq(ArrayAccess {
+                                          env: self.env,
+                                          array: self.value,
+                                          index: 0,
+                                          length: length,
+                                      })
+                } else {
+                    visitor.visit_map(ObjectAccess::new(self.env, self.value)?)
+                }
+            }
+            value_type => Err(self.invalid_type(value_type)),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        match napi::type_of(self.env, self.value)? {
+            NapiValueType::Undefined | NapiValueType::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    // accepts `"Variant"` for unit variants and `{"Variant": value}` for all
+    // variants
+    fn deserialize_enum<V>(self,
+                           _name: &'static str,
+                           _variants: &'static [&'static str],
+                           visitor: V)
+                           -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        match napi::type_of(self.env, self.value)? {
+            NapiValueType::String => {
+                let variant = napi::get_value_string_utf8(self.env, self.value)?;
+                visitor.visit_enum(variant.into_deserializer())
+            }
+            NapiValueType::Object => {
+                let names = napi::get_property_names(self.env, self.value)?;
+                if napi::get_array_length(self.env, names)? != 1 {
+                    return Err(de::Error::custom("expected an object with a single key for an \
+                                                  enum variant"));
+                }
+                let variant = napi::get_element(self.env, names, 0)?;
+                let value = napi::get_property(self.env, self.value, variant)?;
+                visitor.visit_enum(VariantAccess {
+                                       env: self.env,
+                                       variant: variant,
+                                       value: value,
+                                   })
+            }
+            value_type => Err(self.invalid_type(value_type)),
+        }
+    }
+
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier
+    }
+}
+
+fn buffer_data(env: NapiEnv, value: NapiValue) -> Result<Vec<u8>> {
+    let (data, length) = napi::get_buffer_info(env, value)?;
+    if length == 0 {
+        return Ok(Vec::new());
+    }
+    Ok(unsafe { ::std::slice::from_raw_parts(data, length) }.to_vec())
+}
+
+struct ArrayAccess {
+    env: NapiEnv,
+    array: NapiValue,
+    index: usize,
+    length: usize,
+}
+
+impl<'de> de::SeqAccess<'de> for ArrayAccess {
+    type Error = NapiError;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+        where T: de::DeserializeSeed<'de>
+    {
+        if self.index >= self.length {
+            return Ok(None);
+        }
+        let value = napi::get_element(self.env, self.array, self.index)?;
+        self.index += 1;
+        seed.deserialize(Deserializer::new(self.env, value)).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.length - self.index)
+    }
+}
+
+// walks the enumerable string properties of an object
+struct ObjectAccess {
+    env: NapiEnv,
+    object: NapiValue,
+    names: NapiValue,
+    index: usize,
+    length: usize,
+    name: Option<NapiValue>,
+}
+
+impl ObjectAccess {
+    fn new(env: NapiEnv, object: NapiValue) -> Result<ObjectAccess> {
+        let names = napi::get_property_names(env, object)?;
+        let length = napi::get_array_length(env, names)?;
+        Ok(ObjectAccess {
+               env: env,
+               object: object,
+               names: names,
+               index: 0,
+               length: length,
+               name: None,
+           })
+    }
+}
+
+impl<'de> de::MapAccess<'de> for ObjectAccess {
+    type Error = NapiError;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+        where K: de::DeserializeSeed<'de>
+    {
+        if self.index >= self.length {
+            return Ok(None);
+        }
+        let name = napi::get_element(self.env, self.names, self.index)?;
+        self.index += 1;
+        self.name = Some(name);
+        let key = napi::get_value_string_utf8(self.env, name)?;
+        seed.deserialize(MapKey { key: key }).map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+        where V: de::DeserializeSeed<'de>
+    {
+        let name = self.name
+            .take()
+            .expect("next_value_seed called before next_key_seed");
+        let value = napi::get_property(self.env, self.object, name)?;
+        seed.deserialize(Deserializer::new(self.env, value))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.length - self.index)
+    }
+}
+
+// property names are always strings, so keys of other types are parsed from
+// them like serde_json does, e.g. to deserialize a `HashMap<u32, _>`
+struct MapKey {
+    key: String,
+}
+
+macro_rules! deserialize_parsed_key {
+    ($lifetime:tt; $($method:ident => $visit:ident),*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value>
+                where V: de::Visitor<$lifetime>
+            {
+                match self.key.parse() {
+                    Ok(key) => visitor.$visit(key),
+                    Err(_) => Err(de::Error::invalid_value(de::Unexpected::Str(&self.key), &visitor)),
+                }
+            }
+        )*
+    }
+}
+
+impl<'de> de::Deserializer<'de> for MapKey {
+    type Error = NapiError;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        visitor.visit_string(self.key)
+    }
+
+    deserialize_parsed_key! { 'de;
+        deserialize_i8 => visit_i8, deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32, deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128, deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16, deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64, deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32, deserialize_f64 => visit_f64
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(self,
+                           _name: &'static str,
+                           _variants: &'static [&'static str],
+                           visitor: V)
+                           -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        visitor.visit_enum(self.key.into_deserializer())
+    }
+
+    forward_to_deserialize_any! {
+        bool char str string bytes byte_buf unit unit_struct seq tuple tuple_struct
+        map struct identifier ignored_any
+    }
+}
+
+struct VariantAccess {
+    env: NapiEnv,
+    variant: NapiValue,
+    value: NapiValue,
+}
+
+impl<'de> de::EnumAccess<'de> for VariantAccess {
+    type Error = NapiError;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self)>
+        where V: de::DeserializeSeed<'de>
+    {
+        let variant = seed.deserialize(Deserializer::new(self.env, self.variant))?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for VariantAccess {
+    type Error = NapiError;
+
+    fn unit_variant(self) -> Result<()> {
+        de::Deserialize::deserialize(Deserializer::new(self.env, self.value))
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
+        where T: de::DeserializeSeed<'de>
+    {
+        seed.deserialize(Deserializer::new(self.env, self.value))
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        de::Deserializer::deserialize_seq(Deserializer::new(self.env, self.value), visitor)
+    }
+
+    fn struct_variant<V>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+        where V: de::Visitor<'de>
+    {
+        de::Deserializer::deserialize_map(Deserializer::new(self.env, self.value), visitor)
+    }
+}
@@ -0,0 +1,246 @@
+use std::boxed::Box;
+use std::marker::PhantomData;
+use std::sync::{Arc, Condvar, Mutex};
+use std::{ptr, os};
+
+use node_api_sys::*;
+
+use napi::{self, NapiEnv, NapiValue};
+use napi_value::IntoNapiValue;
+use napi_async_context::AsyncContext;
+use error::{Result, NapiError};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CallMode {
+    // waits for room in the queue if it is full
+    Blocking,
+    // fails with `NapiErrorType::QueueFull` if the queue is full
+    NonBlocking,
+}
+
+impl From<CallMode> for napi_threadsafe_function_call_mode {
+    fn from(mode: CallMode) -> Self {
+        match mode {
+            CallMode::Blocking => napi_threadsafe_function_call_mode::napi_tsfn_blocking,
+            CallMode::NonBlocking => napi_threadsafe_function_call_mode::napi_tsfn_nonblocking,
+        }
+    }
+}
+
+type Converter<T> = Box<Fn(NapiEnv, T) -> Result<Vec<NapiValue>> + Send>;
+
+// only touched on the main thread, by `call_js` and `finalize_context`
+struct Context<T> {
+    converter: Converter<T>,
+    // calls are made in the async context the function was created in
+    async_context: AsyncContext,
+    queue_room: Arc<QueueRoom>,
+}
+
+// counts the values taken off the queue, for blocking calls to wait on. N-API
+// only wakes up one of the threads blocked on a full queue when it stops being
+// full, so with several of them the others could wait forever.
+#[derive(Debug, Default)]
+struct QueueRoom {
+    dispatched: Mutex<u64>,
+    condvar: Condvar,
+}
+
+impl QueueRoom {
+    fn dispatched(&self) -> u64 {
+        *self.dispatched.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn notify(&self) {
+        *self.dispatched.lock().unwrap_or_else(|err| err.into_inner()) += 1;
+        self.condvar.notify_all();
+    }
+
+    // waits until more than `dispatched` values have been taken off the queue
+    fn wait(&self, dispatched: u64) {
+        let mut current = self.dispatched.lock().unwrap_or_else(|err| err.into_inner());
+        while *current == dispatched {
+            current = self.condvar.wait(current).unwrap_or_else(|err| err.into_inner());
+        }
+    }
+}
+
+/// A handle to a JS function that can be called from any thread. Calls are
+/// queued and the function is invoked on the main JS thread, with each queued
+/// value converted into the call arguments there.
+///
+/// Cloning the handle acquires the underlying thread-safe function again, and
+/// the function is released once the last clone has been dropped.
+#[derive(Debug)]
+pub struct ThreadsafeFunction<T: Send + 'static> {
+    tsfn: napi_threadsafe_function,
+    queue_room: Arc<QueueRoom>,
+    _value: PhantomData<fn(T)>,
+}
+
+unsafe impl<T: Send + 'static> Send for ThreadsafeFunction<T> {}
+unsafe impl<T: Send + 'static> Sync for ThreadsafeFunction<T> {}
+
+impl<T> ThreadsafeFunction<T>
+    where T: IntoNapiValue + Send + 'static
+{
+    // calls the function with the queued value as its only argument
+    pub fn new(env: NapiEnv, func: NapiValue, max_queue_size: usize) -> Result<Self> {
+        Self::with_converter(env, func, max_queue_size, |env, value: T| {
+            Ok(vec![value.into_napi_value(env)?])
+        })
+    }
+}
+
+impl<T> ThreadsafeFunction<T>
+    where T: Send + 'static
+{
+    // a `max_queue_size` of 0 means the queue is unbounded
+    pub fn with_converter<C>(env: NapiEnv,
+                             func: NapiValue,
+                             max_queue_size: usize,
+                             converter: C)
+                             -> Result<Self>
+        where C: Fn(NapiEnv, T) -> Result<Vec<NapiValue>> + Send + 'static
+    {
+        let name = napi::create_string_utf8(env, "node_api::ThreadsafeFunction")?;
+        let async_context = AsyncContext::new(env, "node_api::ThreadsafeFunction")?;
+        let queue_room = Arc::new(QueueRoom::default());
+        let context = Box::new(Context {
+                                   converter: Box::new(converter) as Converter<T>,
+                                   async_context: async_context,
+                                   queue_room: queue_room.clone(),
+                               });
+        let context = Box::into_raw(context) as *mut os::raw::c_void;
+        let mut tsfn: napi_threadsafe_function = ptr::null_mut();
+        let status = unsafe {
+            napi_create_threadsafe_function(env,
+                                            func,
+                                            0,
+                                            name,
+                                            max_queue_size,
+                                            1,
+                                            context,
+                                            Some(finalize_context::<T>),
+                                            context,
+                                            Some(call_js::<T>),
+                                            &mut tsfn)
+        };
+        if let Err(err) = napi::napi_either(env, status, ()) {
+            unsafe { drop(Box::from_raw(context as *mut Context<T>)) };
+            return Err(err);
+        }
+        Ok(ThreadsafeFunction {
+               tsfn: tsfn,
+               queue_room: queue_room,
+               _value: PhantomData,
+           })
+    }
+
+    // blocking calls wait on `queue_room` rather than in N-API, see `QueueRoom`
+    pub fn call(&self, value: T, mode: CallMode) -> Result<()> {
+        let data = Box::into_raw(Box::new(value)) as *mut os::raw::c_void;
+        loop {
+            let dispatched = self.queue_room.dispatched();
+            let status = unsafe {
+                napi_call_threadsafe_function(self.tsfn,
+                                              data,
+                                              CallMode::NonBlocking.into())
+            };
+            match status {
+                napi_status::napi_ok => return Ok(()),
+                napi_status::napi_queue_full if mode == CallMode::Blocking => {
+                    self.queue_room.wait(dispatched)
+                }
+                _ => {
+                    // the value was not queued, so ownership stays with us
+                    unsafe { drop(Box::from_raw(data as *mut T)) };
+                    return Err(NapiError::from(status));
+                }
+            }
+        }
+    }
+
+    // acquires the function again for another handle, which fails once the
+    // function is closing, e.g. while the env shuts down
+    pub fn try_clone(&self) -> Result<Self> {
+        let status = unsafe { napi_acquire_threadsafe_function(self.tsfn) };
+        if status != napi_status::napi_ok {
+            return Err(NapiError::from(status));
+        }
+        Ok(ThreadsafeFunction {
+               tsfn: self.tsfn,
+               queue_room: self.queue_room.clone(),
+               _value: PhantomData,
+           })
+    }
+
+    // lets the event loop exit while the function is still alive, may only be
+    // called on the main thread
+    pub fn unref(&self, env: NapiEnv) -> Result<()> {
+        let status = unsafe { napi_unref_threadsafe_function(env, self.tsfn) };
+        napi::napi_either(env, status, ())
+    }
+
+    // keeps the event loop alive as long as the function is, which is the
+    // default; may only be called on the main thread
+    pub fn ref_(&self, env: NapiEnv) -> Result<()> {
+        let status = unsafe { napi_ref_threadsafe_function(env, self.tsfn) };
+        napi::napi_either(env, status, ())
+    }
+}
+
+// panics if the function is closing, use `try_clone` where that can happen,
+// e.g. on worker threads that may outlive the env
+impl<T> Clone for ThreadsafeFunction<T>
+    where T: Send + 'static
+{
+    fn clone(&self) -> Self {
+        self.try_clone().expect("error acquiring threadsafe function")
+    }
+}
+
+impl<T> Drop for ThreadsafeFunction<T>
+    where T: Send + 'static
+{
+    fn drop(&mut self) {
+        unsafe {
+            napi_release_threadsafe_function(self.tsfn,
+                                             napi_threadsafe_function_release_mode::napi_tsfn_release);
+        }
+    }
+}
+
+unsafe extern "C" fn call_js<T>(env: NapiEnv,
+                                js_callback: NapiValue,
+                                context: *mut os::raw::c_void,
+                                data: *mut os::raw::c_void)
+    where T: Send + 'static
+{
+    let value: Box<T> = Box::from_raw(data as *mut T);
+    let context = &*(context as *mut Context<T>);
+    context.queue_room.notify();
+    // a null env means the environment is shutting down and the queued value
+    // can only be dropped
+    if env == 0 {
+        return;
+    }
+    // `napi_make_callback` would turn an `undefined` receiver into an error,
+    // so the callback is called inside of a callback scope instead
+    let result = (context.converter)(env, *value).and_then(|args| {
+        let undefined = napi::get_undefined(env)?;
+        let _scope = context.async_context.enter()?;
+        napi::call_function(env, undefined, js_callback, &args)
+    });
+    if let Err(err) = result {
+        napi::report_uncaught(env, err);
+    }
+}
+
+unsafe extern "C" fn finalize_context<T>(_env: NapiEnv,
+                                           finalize_data: *mut os::raw::c_void,
+                                           _finalize_hint: *mut os::raw::c_void)
+    where T: Send + 'static
+{
+    drop(Box::from_raw(finalize_data as *mut Context<T>));
+}
@@ -1,28 +1,45 @@
+use std::result::Result as StdResult;
+
 use napi;
-use futures::future;
-use futures::future::Future;
+use futures::Future;
+#[cfg(feature = "compat")]
+use futures::compat::Compat01As03;
 
 use napi::NapiValueType;
 use error::{Result, NapiError, NapiErrorType};
 use napi_futures;
+use napi_call_context::CallContext;
 
+// converts a single JS value
 pub trait FromNapiValue: Sized {
-    fn from_napi_values(napi::NapiEnv, napi::NapiValue) -> Result<Self>;
+    fn from_napi_value(napi::NapiEnv, napi::NapiValue) -> Result<Self>;
 }
 
+// converts the arguments of a function call
 pub trait FromNapiValues: Sized {
     fn from_napi_values(napi::NapiEnv, napi::NapiValue, &[napi::NapiValue]) -> Result<Self>;
+
+    // used when called from JS, for conversions that need more than `this`
+    // and the arguments, e.g. `new.target`
+    fn from_call_context(context: &CallContext) -> Result<Self> {
+        Self::from_napi_values(context.env(), context.raw_this(), context.args())
+    }
 }
 
 
 macro_rules! impl_from_napi_values {
     ($t:ty, $from:expr, $get_value:expr) => {
+        impl FromNapiValue for $t {
+            fn from_napi_value(env: napi::NapiEnv, value: napi::NapiValue) -> Result<$t> {
+                check_napi_type(env, $from, value)?;
+                $get_value(env, value)
+            }
+        }
+
         impl FromNapiValues for $t {
             fn from_napi_values(env: napi::NapiEnv, _: napi::NapiValue, napi_values: &[napi::NapiValue]) -> Result<$t> {
                 check_napi_args_length(env, napi_values, 1)?;
-                let value = napi_values[0];
-                check_napi_type(env, $from, value)?;
-                $get_value(env, value)
+                FromNapiValue::from_napi_value(env, napi_values[0])
             }
         }
     }
@@ -67,6 +84,135 @@ impl<T> FromNapiValues for Vec<T> where T: FromNapiValues {
     }
 }
 
+impl<T> FromNapiValue for Vec<T> where T: FromNapiValue {
+    fn from_napi_value(env: napi::NapiEnv, value: napi::NapiValue) -> Result<Self> {
+        if !napi::is_array(env, value)? {
+            Err(NapiError{error_message: "expected array".to_string(),
+                          engine_error_code: 0,
+                          error_code: NapiErrorType::InvalidArg,
+            })
+        } else {
+            let size = napi::get_array_length(env, value)?;
+            let mut result = Vec::with_capacity(size);
+            for i in 0..size {
+                let ival = napi::get_element(env, value, i)?;
+                result.push(FromNapiValue::from_napi_value(env, ival)?);
+            }
+            Ok(result)
+        }
+    }
+}
+
+// `undefined` and `null` convert to `None`, which makes optional arguments
+// possible when used in an argument tuple
+impl<T> FromNapiValue for Option<T> where T: FromNapiValue {
+    fn from_napi_value(env: napi::NapiEnv, value: napi::NapiValue) -> Result<Self> {
+        match napi::type_of(env, value)? {
+            NapiValueType::Undefined | NapiValueType::Null => Ok(None),
+            _ => T::from_napi_value(env, value).map(Some),
+        }
+    }
+}
+
+// types that JS values can be coerced into, following the JS abstract
+// operations like `ToNumber` or `ToString`
+pub trait Coercible: FromNapiValue {
+    fn coerce(env: napi::NapiEnv, value: napi::NapiValue) -> Result<napi::NapiValue>;
+}
+
+macro_rules! impl_coercible {
+    ($t:ty, $coerce:expr) => {
+        impl Coercible for $t {
+            fn coerce(env: napi::NapiEnv, value: napi::NapiValue) -> Result<napi::NapiValue> {
+                $coerce(env, value)
+            }
+        }
+    }
+}
+
+impl_coercible!(String, napi::coerce_to_string);
+impl_coercible!(i64, napi::coerce_to_number);
+impl_coercible!(u64, napi::coerce_to_number);
+impl_coercible!(bool, napi::coerce_to_bool);
+impl_coercible!(f64, napi::coerce_to_number);
+
+/// Converts a value the way JS would instead of requiring it to already have
+/// the right type, e.g. accepts `"42"` as a number or `0` as a boolean.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Coerced<T>(pub T);
+
+impl<T> FromNapiValue for Coerced<T> where T: Coercible {
+    fn from_napi_value(env: napi::NapiEnv, value: napi::NapiValue) -> Result<Self> {
+        let coerced = T::coerce(env, value)?;
+        T::from_napi_value(env, coerced).map(Coerced)
+    }
+}
+
+impl<T> FromNapiValues for Coerced<T> where T: Coercible {
+    fn from_napi_values(env: napi::NapiEnv, _: napi::NapiValue, napi_values: &[napi::NapiValue]) -> Result<Self> {
+        check_napi_args_length(env, napi_values, 1)?;
+        FromNapiValue::from_napi_value(env, napi_values[0])
+    }
+}
+
+// each tuple element converts the argument at its position, missing arguments
+// are passed as `undefined` like in JS
+macro_rules! impl_from_napi_values_for_tuple {
+    ($($t:ident $i:tt),+) => {
+        impl<$($t),+> FromNapiValues for ($($t,)+)
+            where $($t: FromNapiValue),+
+        {
+            fn from_napi_values(env: napi::NapiEnv, _: napi::NapiValue, napi_values: &[napi::NapiValue]) -> Result<Self> {
+                let undefined = napi::get_undefined(env)?;
+                Ok(($($t::from_napi_value(env, *napi_values.get($i).unwrap_or(&undefined))?,)+))
+            }
+        }
+    }
+}
+
+impl_from_napi_values_for_tuple!(A 0);
+impl_from_napi_values_for_tuple!(A 0, B 1);
+impl_from_napi_values_for_tuple!(A 0, B 1, C 2);
+impl_from_napi_values_for_tuple!(A 0, B 1, C 2, D 3);
+impl_from_napi_values_for_tuple!(A 0, B 1, C 2, D 3, E 4);
+impl_from_napi_values_for_tuple!(A 0, B 1, C 2, D 3, E 4, F 5);
+impl_from_napi_values_for_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+impl_from_napi_values_for_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+
+/// Captures all remaining arguments, either on its own or as the last element
+/// of an argument tuple, e.g. `(String, Rest<f64>)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rest<T>(pub Vec<T>);
+
+impl<T> FromNapiValues for Rest<T> where T: FromNapiValue {
+    fn from_napi_values(env: napi::NapiEnv, _: napi::NapiValue, napi_values: &[napi::NapiValue]) -> Result<Self> {
+        napi_values.iter().map(|value| T::from_napi_value(env, *value)).collect::<Result<Vec<T>>>().map(Rest)
+    }
+}
+
+macro_rules! impl_from_napi_values_for_rest_tuple {
+    ($($t:ident $i:tt),+; $rest:tt) => {
+        impl<$($t,)+ R> FromNapiValues for ($($t,)+ Rest<R>,)
+            where $($t: FromNapiValue,)+ R: FromNapiValue
+        {
+            fn from_napi_values(env: napi::NapiEnv, this: napi::NapiValue, napi_values: &[napi::NapiValue]) -> Result<Self> {
+                let undefined = napi::get_undefined(env)?;
+                let rest = napi_values.get($rest..).unwrap_or(&[]);
+                Ok(($($t::from_napi_value(env, *napi_values.get($i).unwrap_or(&undefined))?,)+
+                    Rest::from_napi_values(env, this, rest)?,))
+            }
+        }
+    }
+}
+
+impl_from_napi_values_for_rest_tuple!(A 0; 1);
+impl_from_napi_values_for_rest_tuple!(A 0, B 1; 2);
+impl_from_napi_values_for_rest_tuple!(A 0, B 1, C 2; 3);
+impl_from_napi_values_for_rest_tuple!(A 0, B 1, C 2, D 3; 4);
+impl_from_napi_values_for_rest_tuple!(A 0, B 1, C 2, D 3, E 4; 5);
+impl_from_napi_values_for_rest_tuple!(A 0, B 1, C 2, D 3, E 4, F 5; 6);
+impl_from_napi_values_for_rest_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6; 7);
+
 fn check_napi_args_length(_env: napi::NapiEnv, napi_values: &[napi::NapiValue], expected_length: usize) -> Result<()> {
     let values_length = napi_values.len();
     if values_length == expected_length {
@@ -133,6 +279,40 @@ impl_into_napi_values!(f64, |env, s| napi::create_number(env, s as f64));
 impl_into_napi_values!(bool,  napi::get_boolean);
 
 
+// converts the arguments for calling a JS function, each tuple element becomes
+// one argument
+pub trait IntoNapiValues {
+    fn into_napi_values(self, env: napi::NapiEnv) -> Result<Vec<napi::NapiValue>>;
+}
+
+impl IntoNapiValues for () {
+    fn into_napi_values(self, _: napi::NapiEnv) -> Result<Vec<napi::NapiValue>> {
+        Ok(Vec::new())
+    }
+}
+
+macro_rules! impl_into_napi_values_for_tuple {
+    ($($t:ident $i:tt),+) => {
+        impl<$($t),+> IntoNapiValues for ($($t,)+)
+            where $($t: IntoNapiValue),+
+        {
+            fn into_napi_values(self, env: napi::NapiEnv) -> Result<Vec<napi::NapiValue>> {
+                Ok(vec![$(self.$i.into_napi_value(env)?),+])
+            }
+        }
+    }
+}
+
+impl_into_napi_values_for_tuple!(A 0);
+impl_into_napi_values_for_tuple!(A 0, B 1);
+impl_into_napi_values_for_tuple!(A 0, B 1, C 2);
+impl_into_napi_values_for_tuple!(A 0, B 1, C 2, D 3);
+impl_into_napi_values_for_tuple!(A 0, B 1, C 2, D 3, E 4);
+impl_into_napi_values_for_tuple!(A 0, B 1, C 2, D 3, E 4, F 5);
+impl_into_napi_values_for_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+impl_into_napi_values_for_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+
+
 impl<'a, T> IntoNapiValue for &'a [T]
     where T: IntoNapiValue + Clone
 {
@@ -165,26 +345,56 @@ impl<T> IntoNapiValue for Vec<T>
     }
 }
 
-impl<T, E> IntoNapiValue for future::BoxFuture<T, E>
-    where T: IntoNapiValue + 'static,
-          E: IntoNapiValue + 'static,
+// errors are thrown, or used as the rejection value of promises
+impl<T> IntoNapiValue for Result<T>
+    where T: IntoNapiValue
+{
+    fn into_napi_value(self, env: napi::NapiEnv) -> Result<napi::NapiValue> {
+        self.and_then(|value| value.into_napi_value(env))
+    }
+}
+
+impl IntoNapiValue for NapiError {
+    fn into_napi_value(self, env: napi::NapiEnv) -> Result<napi::NapiValue> {
+        napi::create_error(env, &self.error_message)
+    }
+}
+
+// what functions created from Rust closures may return: a value converted with
+// `IntoNapiValue`, or a future, which is spawned on the env's executor and
+// returned as a promise for its result. `M` is `ReturnsValue` or
+// `ReturnsFuture` and only keeps the two impls apart.
+pub trait IntoReturnValue<M> {
+    fn into_return_value(self, env: napi::NapiEnv) -> Result<napi::NapiValue>;
+}
+
+pub enum ReturnsValue {}
+pub enum ReturnsFuture {}
+
+impl<T> IntoReturnValue<ReturnsValue> for T
+    where T: IntoNapiValue
+{
+    fn into_return_value(self, env: napi::NapiEnv) -> Result<napi::NapiValue> {
+        self.into_napi_value(env)
+    }
+}
+
+impl<F, T, E> IntoReturnValue<ReturnsFuture> for F
+    where F: Future<Output = StdResult<T, E>> + Send + 'static,
+          T: IntoNapiValue + Send + 'static,
+          E: IntoNapiValue + Send + 'static
+{
+    fn into_return_value(self, env: napi::NapiEnv) -> Result<napi::NapiValue> {
+        napi_futures::spawn_promise(env, self)?.into_napi_value(env)
+    }
+}
+
+#[cfg(feature = "compat")]
+impl<T, E> IntoNapiValue for futures01::future::BoxFuture<T, E>
+    where T: IntoNapiValue + Send + 'static,
+          E: IntoNapiValue + Send + 'static
 {
     fn into_napi_value(self, env: napi::NapiEnv) -> Result<napi::NapiValue> {
-        let obj = napi::create_object(env)?;
-        let state = napi::create_external(env, Box::new(self))?;
-        let then = napi::create_function(env, "then", move |env, this, then_args: napi_futures::ThenArgs<T, E>| {
-            let state = napi::get_named_property(env, this, "state").unwrap();
-            let future: Box<future::BoxFuture<T,E>> = napi::get_value_external(env, state).unwrap();
-            future.then(move |result| {
-                match result {
-                    Ok(val) => (then_args.on_fulfilled)(env, this, val),
-                    Err(err) => (then_args.on_rejected)(env, this, err)
-                }
-                future::result::<(),()>(Ok(())).boxed()
-            }).boxed().wait().unwrap();
-        })?;
-        napi::set_named_property(env, obj, "then", then)?;
-        napi::set_named_property(env, obj, "state", state)?;
-        Ok(obj)
+        napi_futures::spawn_promise(env, Compat01As03::new(self))?.into_napi_value(env)
     }
 }
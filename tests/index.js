@@ -1,5 +1,6 @@
 const nt = require('./node-api');
 const expect = require('chai').expect;
+const AsyncLocalStorage = require('async_hooks').AsyncLocalStorage;
 
 describe("node-api", function() {
     describe("function returns", function() {
@@ -48,6 +49,40 @@ describe("node-api", function() {
             const arr = ["one", "two", "three"];
             expect(nt.receives_arrays(arr)).to.deep.equal(arr);
         });
+        it("can receive coerced values", function() {
+            expect(nt.receives_coerced_values("42", 7, 0)).to.deep.equal(["42", "7", "false"]);
+            expect(nt.receives_coerced_values(true, {}, "yes")).to.deep.equal(["1", "[object Object]", "true"]);
+        });
+        it("can receive rest arguments", function() {
+            expect(nt.joins_rest_arguments(", ", "a", "b", "c")).to.equal("a, b, c");
+            expect(nt.joins_rest_arguments("-")).to.equal("");
+            expect(function() { nt.joins_rest_arguments("-", "a", 1); }).to.throw(Error);
+        });
+        it("receives more than 16 arguments", function() {
+            const numbers = [];
+            for (let i = 1; i <= 20; i++) {
+                numbers.push(i);
+            }
+            expect(nt.sums_rest_arguments.apply(null, numbers)).to.equal(210);
+            expect(nt.counts_arguments.apply(null, numbers)).to.equal(20);
+            expect(nt.counts_arguments()).to.equal(0);
+        });
+        it("receives the call context", function() {
+            const object = {describe: nt.describes_calls};
+            const called = object.describe("called", 1, 2);
+            expect(called.name).to.equal("called");
+            expect(called.construct).to.equal(false);
+            expect(called.argc).to.equal(3);
+            expect(called.this).to.equal(object);
+            const constructed = new nt.describes_calls("constructed");
+            expect(constructed.construct).to.equal(true);
+            expect(constructed.argc).to.equal(1);
+            expect(constructed.this).to.not.equal(object);
+        });
+        it("receives new.target", function() {
+            expect(nt.returns_new_target()).to.equal(undefined);
+            expect(new nt.returns_new_target()).to.equal(nt.returns_new_target);
+        });
     });
     describe("promises", function() {
         it("returns a void promise", function(done) {
@@ -56,5 +91,655 @@ describe("node-api", function() {
                 done();
             });
         });
+        it("returns a genuine promise", function() {
+            expect(nt.returns_promises()).to.be.an.instanceof(Promise);
+        });
+        it("resolves promises synchronously", function() {
+            return nt.resolves_promises("hello").then(function(value) {
+                expect(value).to.equal("hello");
+            });
+        });
+        it("rejects promises with errors", function() {
+            return nt.rejects_promises("failed").then(function() {
+                throw new Error("expected promise to be rejected");
+            }, function(err) {
+                expect(err).to.be.an.instanceof(Error);
+                expect(err.message).to.equal("failed");
+            });
+        });
+        it("rejects promises with errors that fail to convert", function() {
+            return nt.rejects_promises_with_unconvertible_errors().then(function() {
+                throw new Error("expected promise to be rejected");
+            }, function(err) {
+                expect(err).to.be.an.instanceof(Error);
+                expect(err.message).to.include("map keys must be strings or numbers");
+            });
+        });
+        it("does not block the event loop while futures are pending", function() {
+            let ticked = false;
+            setImmediate(function() { ticked = true; });
+            return nt.returns_pending_promises(50).then(function(value) {
+                expect(value).to.equal(50);
+                expect(ticked).to.equal(true);
+            });
+        });
+        it("resolves promises of async functions", function() {
+            return nt.returns_async_values(21).then(function(value) {
+                expect(value).to.equal(42);
+            });
+        });
+        it("resolves promises of futures returned without boxing", function() {
+            const p = nt.returns_unboxed_futures(21);
+            expect(p).to.be.an.instanceof(Promise);
+            return p.then(function(value) {
+                expect(value).to.equal(42);
+            });
+        });
+        it("resolves promises of futures 0.1 futures", function() {
+            return nt.returns_legacy_promises(7).then(function(value) {
+                expect(value).to.equal(7);
+            });
+        });
+        it("resolves promises from other threads", function() {
+            return nt.resolves_promises_from_threads(21).then(function(value) {
+                expect(value).to.equal(42);
+            });
+        });
+    });
+    describe("async tasks", function() {
+        it("resolves with the computed value", function() {
+            return nt.computes_async_tasks(10).then(function(value) {
+                expect(value).to.equal(55);
+            });
+        });
+        it("rejects when the computation fails", function() {
+            return nt.computes_async_tasks(100).then(function() {
+                throw new Error("expected promise to be rejected");
+            }, function(err) {
+                expect(err.message).to.equal("input too large");
+            });
+        });
+        it("rejects when the task is cancelled", function() {
+            return nt.cancels_async_tasks().then(function() {
+                throw new Error("expected promise to be rejected");
+            }, function(err) {
+                expect(err).to.be.an.instanceof(Error);
+            });
+        });
+    });
+    describe("abort signals", function() {
+        function expectAbortError(promise) {
+            return promise.then(function() {
+                throw new Error("expected promise to be rejected");
+            }, function(err) {
+                expect(err.name).to.equal("AbortError");
+            });
+        }
+        it("rejects async functions when aborted", function() {
+            const controller = new AbortController();
+            const p = nt.aborts_async_functions(1000, controller.signal);
+            controller.abort();
+            return expectAbortError(p);
+        });
+        it("rejects async tasks when aborted", function() {
+            const controller = new AbortController();
+            const p = nt.aborts_async_tasks(1000, controller.signal);
+            controller.abort();
+            return expectAbortError(p);
+        });
+        it("rejects running async tasks without waiting for them", function() {
+            const controller = new AbortController();
+            const p = nt.aborts_async_tasks(1000, controller.signal);
+            return new Promise(function(resolve) { setTimeout(resolve, 50); }).then(function() {
+                const start = Date.now();
+                controller.abort();
+                return expectAbortError(p).then(function() {
+                    expect(Date.now() - start).to.be.below(500);
+                });
+            });
+        });
+        it("rejects right away if the signal was already aborted", function() {
+            const controller = new AbortController();
+            controller.abort();
+            return expectAbortError(nt.aborts_async_tasks(0, controller.signal));
+        });
+        it("resolves if not aborted", function() {
+            const controller = new AbortController();
+            return nt.aborts_async_functions(1, controller.signal).then(function(value) {
+                expect(value).to.equal(1);
+            });
+        });
+        it("makes the signal optional", function() {
+            return nt.aborts_async_tasks(1);
+        });
+        it("removes its listeners once the work has completed", function() {
+            const { getEventListeners } = require("events");
+            const controller = new AbortController();
+            return Promise.all([
+                nt.aborts_async_tasks(1, controller.signal),
+                nt.aborts_async_functions(1, controller.signal),
+            ]).then(function() {
+                // the listeners of async functions are removed from the main thread
+                return new Promise(setImmediate);
+            }).then(function() {
+                expect(getEventListeners(controller.signal, "abort").length).to.equal(0);
+            });
+        });
+        it("rejects objects that are not abort signals", function() {
+            expect(function() {
+                nt.aborts_async_tasks(1, { aborted: false });
+            }).to.throw(/expected an AbortSignal/);
+        });
+        it("rejects look-alike signals without a global AbortSignal", function() {
+            const AbortSignal = global.AbortSignal;
+            delete global.AbortSignal;
+            try {
+                expect(function() {
+                    nt.aborts_async_tasks(1, { aborted: false, addEventListener() {} });
+                }).to.throw(/expected an AbortSignal/);
+            } finally {
+                global.AbortSignal = AbortSignal;
+            }
+        });
+    });
+    describe("properties", function() {
+        it("defines read-only values", function() {
+            expect(nt.properties.VERSION).to.equal("1.0.0");
+            expect(function() {
+                "use strict";
+                nt.properties.VERSION = "2.0.0";
+            }).to.throw(TypeError);
+        });
+        it("defines writable values", function() {
+            nt.properties.counter = 5;
+            expect(nt.properties.counter).to.equal(5);
+        });
+        it("defines enumerable properties", function() {
+            expect(Object.keys(nt.properties)).to.deep.equal(["VERSION", "stored"]);
+        });
+        it("defines getters and setters", function() {
+            expect(nt.properties.stored).to.equal(0);
+            nt.properties.stored = 42;
+            expect(nt.properties.stored).to.equal(42);
+        });
+        it("throws when setters receive invalid values", function() {
+            expect(function() { nt.properties.stored = "nope"; }).to.throw(Error);
+        });
+        it("defines methods", function() {
+            expect(nt.properties.add(1, 2)).to.equal(3);
+        });
+        it("keeps methods and accessors working after their object is collected", function() {
+            let properties = nt.creates_properties();
+            const add = properties.add;
+            const { get, set } = Object.getOwnPropertyDescriptor(properties, "stored");
+            properties = null;
+            global.gc();
+            return new Promise(setImmediate).then(function() {
+                global.gc();
+                expect(add(1, 2)).to.equal(3);
+                const other = {};
+                set.call(other, 7);
+                expect(get.call(other)).to.equal(7);
+            });
+        });
+    });
+    describe("exports", function() {
+        it("exports values", function() {
+            expect(nt.VERSION).to.equal("1.0.0");
+        });
+    });
+    describe("classes", function() {
+        it("constructs instances", function() {
+            const counter = new nt.Counter(5);
+            expect(counter).to.be.an.instanceof(nt.Counter);
+            expect(counter.value).to.equal(5);
+        });
+        it("converts constructor arguments", function() {
+            expect(new nt.Counter().value).to.equal(0);
+            expect(function() { new nt.Counter("five"); }).to.throw(Error);
+        });
+        it("calls methods on the wrapped value", function() {
+            const counter = new nt.Counter(1);
+            expect(counter.increment()).to.equal(2);
+            expect(counter.value).to.equal(2);
+            expect(new nt.Counter().value).to.equal(0);
+        });
+        it("sets accessors on the wrapped value", function() {
+            const counter = new nt.Counter();
+            counter.value = 10;
+            expect(counter.increment()).to.equal(11);
+        });
+        it("throws when called without new", function() {
+            expect(function() { nt.Counter(); }).to.throw(Error);
+        });
+        it("throws when methods are called on other objects", function() {
+            const increment = nt.Counter.prototype.increment;
+            expect(function() { increment.call({}); }).to.throw(Error);
+        });
+        it("throws when methods are called on objects wrapping other values", function() {
+            const increment = nt.Counter.prototype.increment;
+            expect(function() { increment.call(nt.wraps_objects()); }).to.throw(Error);
+        });
+        it("keeps prototype methods working after the class is collected", function() {
+            let Counter = nt.defines_classes();
+            const increment = Counter.prototype.increment;
+            const { get } = Object.getOwnPropertyDescriptor(Counter.prototype, "value");
+            Counter = null;
+            global.gc();
+            return new Promise(setImmediate).then(function() {
+                global.gc();
+                expect(function() { increment.call({}); }).to.throw(Error);
+                expect(function() { get.call({}); }).to.throw(Error);
+            });
+        });
+    });
+    describe("instance data", function() {
+        it("keeps separate data for each worker", function(done) {
+            const { Worker } = require("worker_threads");
+            expect(nt.counts_calls_per_env()).to.equal(1);
+            const worker = new Worker(
+                "const nt = require(" + JSON.stringify(require.resolve("./node-api")) + ");" +
+                "nt.counts_calls_per_env();" +
+                "require('worker_threads').parentPort.postMessage(nt.counts_calls_per_env());",
+                { eval: true });
+            worker.on("message", function(count) {
+                expect(count).to.equal(2);
+                expect(nt.counts_calls_per_env()).to.equal(2);
+                done();
+            });
+            worker.on("error", done);
+        });
+    });
+    describe("cleanup hooks", function() {
+        function inWorker(code, done) {
+            const { Worker } = require("worker_threads");
+            const worker = new Worker(
+                "const nt = require(" + JSON.stringify(require.resolve("./node-api")) + ");" + code,
+                { eval: true });
+            let error;
+            worker.on("error", function(err) { error = err; });
+            worker.on("exit", function() { done(error); });
+        }
+        it("runs hooks when an env shuts down", function(done) {
+            const before = nt.counts_cleanup_hooks();
+            inWorker("nt.registers_cleanup_hooks(false);", function(err) {
+                expect(nt.counts_cleanup_hooks()).to.equal(before + 1);
+                done(err);
+            });
+        });
+        it("does not run removed hooks", function(done) {
+            const before = nt.counts_cleanup_hooks();
+            inWorker("nt.registers_cleanup_hooks(true);", function(err) {
+                expect(nt.counts_cleanup_hooks()).to.equal(before);
+                done(err);
+            });
+        });
+    });
+    describe("references", function() {
+        it("keeps referenced values alive", function() {
+            nt.stores_references({ answer: 42 });
+            expect(nt.loads_references()).to.deep.equal({ answer: 42 });
+        });
+    });
+    describe("wrapped values", function() {
+        it("reads wrapped values without removing them", function() {
+            const object = {};
+            nt.wraps_values(object, 7);
+            expect(nt.reads_wrapped_values(object)).to.equal(7);
+            expect(nt.reads_wrapped_values(object)).to.equal(7);
+        });
+        it("removes wrapped values", function() {
+            const object = {};
+            nt.wraps_values(object, 7);
+            expect(nt.removes_wrapped_values(object)).to.equal(7);
+            expect(function() { nt.reads_wrapped_values(object); }).to.throw(Error);
+        });
+        it("frees wrapped values of collected objects", function() {
+            nt.wraps_values({}, 7);
+            global.gc();
+        });
+    });
+    describe("symbols", function() {
+        it("creates symbols", function() {
+            const symbol = nt.creates_symbols("native");
+            expect(typeof symbol).to.equal("symbol");
+            expect(symbol.description).to.equal("native");
+            expect(nt.creates_symbols()).to.not.equal(nt.creates_symbols());
+        });
+        it("describes symbols", function() {
+            expect(nt.describes_symbols(Symbol("foo"))).to.equal("foo");
+            expect(nt.describes_symbols(Symbol())).to.equal("");
+        });
+        it("uses symbols as property keys", function() {
+            const key = Symbol("key");
+            const object = { [key]: 1 };
+            expect(nt.has_properties(object, key)).to.equal(true);
+            expect(nt.deletes_properties(object, key)).to.equal(true);
+            expect(nt.has_properties(object, key)).to.equal(false);
+        });
+        it("implements the iteration protocol", function() {
+            const range = nt.creates_iterables(3);
+            expect(Array.from(range)).to.deep.equal([0, 1, 2]);
+            expect(Object.prototype.toString.call(range)).to.equal("[object Range]");
+        });
+    });
+    describe("object model", function() {
+        it("constructs builtin classes", function() {
+            const date = nt.creates_dates(0);
+            expect(date).to.be.an.instanceof(Date);
+            expect(date.getTime()).to.equal(0);
+        });
+        it("constructs classes passed in", function() {
+            const map = nt.constructs_instances(Map, [["a", 1]]);
+            expect(map.get("a")).to.equal(1);
+            const counter = nt.constructs_instances(nt.Counter, 3);
+            expect(counter.value).to.equal(3);
+        });
+        it("checks instanceof", function() {
+            expect(nt.checks_instances(new Date(), Date)).to.equal(true);
+            expect(nt.checks_instances({}, Date)).to.equal(false);
+            expect(nt.checks_instances(new nt.Counter(), nt.Counter)).to.equal(true);
+        });
+        it("compares strictly", function() {
+            const object = {};
+            expect(nt.compares_values(object, object)).to.equal(true);
+            expect(nt.compares_values({}, {})).to.equal(false);
+            expect(nt.compares_values(1, "1")).to.equal(false);
+        });
+        it("gets prototypes", function() {
+            expect(nt.gets_prototypes([])).to.equal(Array.prototype);
+        });
+    });
+    describe("typed values", function() {
+        it("downcasts to the matching type", function() {
+            expect(nt.describes_values([1, 2, 3])).to.equal("array of 3");
+            expect(nt.describes_values(Buffer.from([1, 2]))).to.equal("buffer [1, 2]");
+            expect(nt.describes_values(new Float64Array(4))).to.equal("Float64 array of 4");
+            expect(nt.describes_values(new Date(5))).to.equal("date 5");
+            expect(nt.describes_values(new Error("boom"))).to.equal("error boom");
+            expect(nt.describes_values(Promise.resolve())).to.equal("promise");
+            expect(nt.describes_values(function() {})).to.equal("function");
+            expect(nt.describes_values({ a: 1, b: 2 })).to.equal("object with 2 keys");
+            expect(nt.describes_values("hi")).to.equal("string hi");
+            expect(nt.describes_values(1.5)).to.equal("number 1.5");
+            expect(nt.describes_values(null)).to.equal("Null");
+        });
+        it("throws on mismatched types", function() {
+            expect(nt.sums_arrays([1, 2, 3])).to.equal(6);
+            expect(function() { nt.sums_arrays({}); }).to.throw(Error);
+            expect(function() { nt.sums_arrays([1, "2"]); }).to.throw(Error);
+        });
+        it("reads the bytes of typed arrays", function() {
+            const array = new Uint16Array([1, 0x0102, 0x0304]).subarray(1);
+            expect(nt.reads_typed_arrays(array)).to.deep.equal([0x02, 0x01, 0x04, 0x03]);
+            expect(nt.reads_typed_arrays(new Float64Array(0))).to.deep.equal([]);
+        });
+        it("creates buffers", function() {
+            const buffer = nt.creates_buffers("hi");
+            expect(Buffer.isBuffer(buffer)).to.equal(true);
+            expect(buffer.toString()).to.equal("hi");
+        });
+        it("keeps the values of externals", function() {
+            const external = nt.creates_externals("kept");
+            expect(nt.reads_externals(external)).to.equal("kept");
+            expect(function() { nt.reads_externals({}); }).to.throw(Error);
+        });
+        it("converts promises", function() {
+            return nt.creates_js_promises("settled").then(function(value) {
+                expect(value).to.equal("settled");
+            });
+        });
+    });
+    describe("serde", function() {
+        const order = {
+            id: 7,
+            items: [["apple", 0.5], ["pear", 1.25]],
+            note: null,
+            status: {Shipped: {tracking: "XY123"}},
+            signature: Buffer.from([1, 2, 3])
+        };
+        it("round-trips serde types", function() {
+            const result = nt.round_trips_orders(order);
+            expect(result.id).to.equal(7);
+            expect(result.items).to.deep.equal([["apple", 0.5], ["pear", 1.25]]);
+            expect(result.note).to.equal(null);
+            expect(result.status).to.deep.equal({Shipped: {tracking: "XY123"}});
+            expect(Buffer.isBuffer(result.signature)).to.equal(true);
+            expect([...result.signature]).to.deep.equal([1, 2, 3]);
+        });
+        it("converts enum variants", function() {
+            const pending = Object.assign({}, order, {status: "Pending"});
+            expect(nt.round_trips_orders(pending).status).to.equal("Pending");
+            const cancelled = Object.assign({}, order, {status: {Cancelled: "late"}});
+            expect(nt.round_trips_orders(cancelled).status).to.deep.equal({Cancelled: "late"});
+        });
+        it("deserializes with from_js", function() {
+            expect(nt.totals_orders(order)).to.equal(1.75);
+        });
+        it("serializes maps with to_js", function() {
+            expect(nt.counts_words("a b a")).to.deep.equal({a: 2, b: 1});
+        });
+        it("round-trips maps with numeric keys", function() {
+            expect(nt.round_trips_numeric_keys({1: "one", 20: "twenty"}))
+                .to.deep.equal({1: "one", 20: "twenty"});
+            expect(function() { nt.round_trips_numeric_keys({x: "x"}); }).to.throw(Error);
+        });
+        it("round-trips strings with 0 bytes", function() {
+            expect(nt.round_trips_string_maps({"a\0b": "c\0d"})).to.deep.equal({"a\0b": "c\0d"});
+        });
+        it("deserializes typed arrays other than buffers as numbers", function() {
+            expect(nt.sums_numbers(new Float64Array([0.5, 1.25]))).to.equal(1.75);
+            expect(nt.sums_numbers([0.5, 1.25])).to.equal(1.75);
+        });
+        it("rejects values that do not match", function() {
+            expect(function() { nt.totals_orders({id: "7"}); }).to.throw(Error);
+            const fractional = Object.assign({}, order, {id: 1.5});
+            expect(function() { nt.round_trips_orders(fractional); }).to.throw(Error);
+            const withFunction = Object.assign({}, order, {note: function() {}});
+            expect(function() { nt.round_trips_orders(withFunction); }).to.throw(/cannot deserialize/);
+        });
+    });
+    describe("json values", function() {
+        it("round-trips JSON values", function() {
+            const value = {a: [1, -2, 3.5, "four", true, null], b: {c: {}}, "": []};
+            expect(nt.round_trips_json(value)).to.deep.equal(value);
+            expect(nt.round_trips_json("text")).to.equal("text");
+            expect(nt.stringifies_json({n: 1, f: 1.5})).to.equal('{"f":1.5,"n":1}');
+        });
+        it("converts values like JSON.stringify", function() {
+            expect(nt.round_trips_json({a: undefined, b: NaN, c: Infinity}))
+                .to.deep.equal({b: null, c: null});
+            expect(nt.round_trips_json({date: new Date(0)}))
+                .to.deep.equal({date: "1970-01-01T00:00:00.000Z"});
+            expect(nt.round_trips_json({toJSON: function() { return [1]; }})).to.deep.equal([1]);
+            const shared = {x: 1};
+            expect(nt.round_trips_json([shared, shared])).to.deep.equal([{x: 1}, {x: 1}]);
+        });
+        it("only converts own enumerable properties", function() {
+            const value = Object.create({inherited: 1}, {
+                own: {value: 2, enumerable: true},
+                hidden: {value: 3, enumerable: false},
+            });
+            expect(nt.round_trips_json(value)).to.deep.equal({own: 2});
+        });
+        it("keeps 0 bytes in keys and strings", function() {
+            const value = {"a\0b": "c\0d"};
+            expect(nt.round_trips_json(value)).to.deep.equal(value);
+            expect(nt.round_trips_json("\0")).to.equal("\0");
+        });
+        it("rejects functions and symbols", function() {
+            expect(function() { nt.round_trips_json({a: [0, function() {}]}); })
+                .to.throw(/cannot convert a Function to JSON at \$\.a\[1\]/);
+            expect(function() { nt.round_trips_json({s: Symbol()}); })
+                .to.throw(/cannot convert a Symbol to JSON at \$\.s/);
+        });
+        it("rejects cyclic values", function() {
+            const cyclic = {a: {}};
+            cyclic.a.b = cyclic;
+            expect(function() { nt.round_trips_json(cyclic); })
+                .to.throw(/cannot convert a cyclic object to JSON at \$\.a\.b/);
+        });
+    });
+    describe("dates", function() {
+        const day = 24 * 60 * 60 * 1000;
+        it("converts dates to and from SystemTime", function() {
+            expect(nt.returns_epoch()).to.be.an.instanceof(Date);
+            expect(nt.returns_epoch().getTime()).to.equal(0);
+            expect(nt.adds_days(new Date(1000), 2).getTime()).to.equal(1000 + 2 * day);
+        });
+        it("converts dates before the epoch", function() {
+            expect(nt.adds_days(new Date(-3 * day), 1).getTime()).to.equal(-2 * day);
+            expect(nt.adds_days(new Date(0), -1).getTime()).to.equal(-day);
+            expect(nt.returns_just_before_epoch().getTime()).to.equal(-1);
+        });
+        it("rejects invalid dates", function() {
+            expect(function() { nt.adds_days(new Date(NaN), 1); }).to.throw(/invalid Date/);
+            expect(function() { nt.adds_days(0, 1); }).to.throw(/expected Date/);
+            expect(function() { nt.adds_days(new Date(8.64e15), 1); }).to.throw(/out of the range/);
+        });
+    });
+    describe("function lifetimes", function() {
+        it("can call functions many times", function() {
+            for (let i = 0; i < 10000; i++) {
+                expect(nt.receives_u64(i)).to.equal(i);
+            }
+            const closure = nt.creates_closures(42);
+            for (let i = 0; i < 10000; i++) {
+                expect(closure()).to.equal(42);
+            }
+        });
+        it("drops closures once their functions are collected", function() {
+            for (let i = 0; i < 100; i++) {
+                expect(nt.creates_closures(i)()).to.equal(i);
+            }
+            global.gc();
+            // finalizers may run after the collection
+            return new Promise(setImmediate).then(function() {
+                global.gc();
+                return new Promise(setImmediate);
+            }).then(function() {
+                expect(nt.counts_dropped_closures()).to.be.above(0);
+            });
+        });
+    });
+    describe("stateful functions", function() {
+        it("keeps the state of FnMut closures", function() {
+            expect(nt.counts_own_calls()).to.equal(1);
+            expect(nt.counts_own_calls()).to.equal(2);
+            const first = nt.creates_counters(0);
+            const second = nt.creates_counters(10);
+            expect(first()).to.equal(1);
+            expect(first()).to.equal(2);
+            expect(second()).to.equal(11);
+        });
+        it("throws when FnMut closures are called reentrantly", function() {
+            expect(function() {
+                nt.calls_back_into_itself(function() {
+                    nt.calls_back_into_itself(function() {});
+                });
+            }).to.throw(/reentrantly/);
+            nt.calls_back_into_itself(function() {});
+        });
+        it("passes data attached to functions", function() {
+            expect(nt.creates_tagged_functions("a")("b")).to.equal("a: b");
+            expect(nt.creates_tagged_functions("c")("d")).to.equal("c: d");
+        });
+    });
+    describe("calling functions", function() {
+        it("passes converted arguments and returns converted results", function() {
+            expect(nt.calls_functions(function(a, b) { return a + b; }, 1, "2")).to.equal("12");
+        });
+        it("throws when the result has the wrong type", function() {
+            expect(function() { nt.calls_functions(function() { return 1; }, 1, "2"); }).to.throw(Error);
+        });
+        it("passes this", function() {
+            const object = {};
+            expect(nt.calls_functions_with_this(function() { return this; }, object)).to.equal(object);
+        });
+        it("calls methods", function() {
+            const object = { value: 40, add: function(n) { return this.value + n; } };
+            expect(nt.calls_methods(object, "add")).to.equal(42);
+            expect(function() { nt.calls_methods(object, "value"); }).to.throw(Error);
+        });
+        it("calls functions with only the result type given", function() {
+            const object = { describe() { return "accepted"; } };
+            expect(nt.calls_predicates(function(a, b) { return a < b; }, object)).to.equal("accepted");
+            expect(nt.calls_predicates(function(a, b) { return a > b; }, object)).to.equal("rejected");
+        });
+    });
+    describe("callbacks", function() {
+        it("invokes callbacks during the call", function() {
+            let args;
+            expect(nt.invokes_callbacks(function(name, value) {
+                args = [name, value];
+                return true;
+            })).to.equal(true);
+            expect(args).to.deep.equal(["answer", 42]);
+        });
+        it("rejects non-function callbacks", function() {
+            expect(function() { nt.invokes_callbacks(42); }).to.throw(Error);
+        });
+        it("invokes persisted callbacks later on", function() {
+            nt.stores_callbacks(function(value) { return value * 2; });
+            expect(nt.invokes_stored_callbacks(21)).to.equal(42);
+        });
+        it("invokes callbacks from other threads", function(done) {
+            const received = [];
+            nt.invokes_callbacks_from_threads(function(index, name) {
+                expect(name).to.equal("thread " + index);
+                received.push(index);
+                if (received.length == 3) {
+                    expect(received.sort()).to.deep.equal([0, 1, 2]);
+                    done();
+                }
+            }, 3);
+        });
+    });
+    describe("threadsafe functions", function() {
+        it("calls functions from other threads", function(done) {
+            const received = [];
+            nt.calls_threadsafe_functions(function(value) {
+                received.push(value);
+                if (received.length == 4) {
+                    expect(received.sort()).to.deep.equal([0, 1, 2, 3]);
+                    done();
+                }
+            }, 4);
+        });
+        it("wakes up every thread blocked on a full queue", function(done) {
+            let received = 0;
+            nt.calls_threadsafe_functions(function() {
+                if (++received == 32) {
+                    done();
+                }
+            }, 32);
+        });
+    });
+    describe("async context", function() {
+        it("calls threadsafe functions in the caller's async context", function(done) {
+            const storage = new AsyncLocalStorage();
+            let calls = 0;
+            storage.run("threadsafe", function() {
+                nt.calls_threadsafe_functions(function() {
+                    expect(storage.getStore()).to.equal("threadsafe");
+                    if (++calls == 2) {
+                        done();
+                    }
+                }, 2);
+            });
+        });
+        it("settles promises from other threads in their own async resource", function() {
+            const types = [];
+            const hook = require('async_hooks').createHook({
+                init: function(id, type) { types.push(type); }
+            }).enable();
+            return nt.resolves_promises_from_threads(1).then(function() {
+                hook.disable();
+                expect(types).to.include("node_api::ThreadsafeDeferred");
+            }, function(err) {
+                hook.disable();
+                throw err;
+            });
+        });
     });
 });
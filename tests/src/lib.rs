@@ -2,56 +2,156 @@
 #[macro_use(napi_module)]
 extern crate node_api;
 extern crate futures;
-extern crate tokio_core;
+extern crate futures01;
 
-use node_api::{NapiEnv, NapiValue, FromNapiValues, IntoNapiValue};
+use node_api::{NapiEnv, NapiValue, FromNapiValues, IntoNapiValue, NapiEnvExt, Reference,
+               JsValue, WellKnownSymbol, Coerced, Rest, Arguments, CallContext, get_null,
+               create_function};
+use node_api::{JsArray, JsBuffer, JsTypedArray, JsDate, JsError, JsPromise, JsFunction, JsObject,
+               JsString, JsNumber, JsUndefined, JsExternal, JsCallback, PersistentCallback};
 use node_api::error::*;
-use node_api::{create_function, get_named_property, set_named_property, create_object,
-               create_external};
+use node_api::{get_named_property, set_named_property, create_object,
+               create_external, wrap, unwrap, remove_wrap};
+use node_api::{Deferred, Promise, AsyncTask, Task, queue_async_task};
+use node_api::{ThreadsafeFunction, CallMode};
+use node_api::{PropertyDescriptor, define_properties, define_class, Exports, Class};
+use node_api::{AbortSignal, AbortableTask, Abortable, abortable};
+use node_api::{Serde, to_js, from_js};
 
-use futures::future;
-use futures::Future;
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use std::result::Result as StdResult;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use tokio_core::reactor::Core;
+use futures::channel::oneshot;
+use futures::future::{self, BoxFuture};
+use futures::{Future, FutureExt, TryFutureExt};
+use serde::{Deserialize, Serialize};
 
-napi_module!("tests", register);
+napi_module!("tests", |exports| {
+    exports.function("returns_objects", returns_objects)?
+        .function("returns_strings", returns_strings)?
+        .function("returns_numbers", returns_numbers)?
+        .function("returns_booleans", returns_booleans)?
+        .function("returns_arrays", returns_arrays)?;
 
-#[no_mangle]
-pub extern "C" fn register(env: NapiEnv,
-                           exports: NapiValue,
-                           module: NapiValue,
-                           _priv: *mut std::os::raw::c_void) {
-    // create_and_attach_event_loop(env, module);
+    exports.function("receives_objects", receives_objects)?
+        .function("receives_strings", receives_strings)?
+        .function("receives_booleans", receives_booleans)?
+        .function("receives_f64", receives_f64)?
+        .function("receives_u64", receives_u64)?
+        .function("receives_i64", receives_i64)?
+        .function("receives_arrays", receives_arrays)?
+        .function("receives_coerced_values", receives_coerced_values)?
+        .function("joins_rest_arguments", joins_rest_arguments)?
+        .function("sums_rest_arguments", sums_rest_arguments)?
+        .function("counts_arguments", counts_arguments)?
+        .function("returns_new_target", returns_new_target)?
+        .function("describes_calls", describes_calls)?;
 
-    register_test(env, "returns_objects", exports, &returns_objects);
-    register_test(env, "returns_strings", exports, &returns_strings);
-    register_test(env, "returns_numbers", exports, &returns_numbers);
-    register_test(env, "returns_booleans", exports, &returns_booleans);
-    register_test(env, "returns_arrays", exports, &returns_arrays);
+    exports.function("returns_promises", returns_promises)?
+        .function("resolves_promises", resolves_promises)?
+        .function("rejects_promises", rejects_promises)?
+        .function("rejects_promises_with_unconvertible_errors",
+                  rejects_promises_with_unconvertible_errors)?
+        .function("resolves_promises_from_threads", resolves_promises_from_threads)?
+        .function("returns_pending_promises", returns_pending_promises)?
+        .function("returns_unboxed_futures", returns_unboxed_futures)?
+        .function("returns_legacy_promises", returns_legacy_promises)?
+        .async_function("returns_async_values", returns_async_values)?;
 
-    register_test(env, "receives_objects", exports, &receives_objects);
-    register_test(env, "receives_strings", exports, &receives_strings);
-    register_test(env, "receives_booleans", exports, &receives_booleans);
-    register_test(env, "receives_f64", exports, &receives_f64);
-    register_test(env, "receives_u64", exports, &receives_u64);
-    register_test(env, "receives_i64", exports, &receives_i64);
-    register_test(env, "receives_arrays", exports, &receives_arrays);
+    exports.function("computes_async_tasks", computes_async_tasks)?
+        .function("cancels_async_tasks", cancels_async_tasks)?
+        .function("aborts_async_tasks", aborts_async_tasks)?
+        .async_function("aborts_async_functions", aborts_async_functions)?;
 
-    register_test(env, "returns_promises", exports, &returns_promises);
-}
+    exports.function("calls_threadsafe_functions", calls_threadsafe_functions)?;
+
+    exports.function("counts_calls_per_env", counts_calls_per_env)?
+        .function("registers_cleanup_hooks", registers_cleanup_hooks)?
+        .function("counts_cleanup_hooks", counts_cleanup_hooks)?
+        .function("stores_references", stores_references)?
+        .function("loads_references", loads_references)?
+        .function("wraps_values", wraps_values)?
+        .function("reads_wrapped_values", reads_wrapped_values)?
+        .function("removes_wrapped_values", removes_wrapped_values)?;
+
+    exports.function("creates_symbols", creates_symbols)?
+        .function("describes_symbols", describes_symbols)?
+        .function("has_properties", has_properties)?
+        .function("deletes_properties", deletes_properties)?
+        .function("creates_iterables", creates_iterables)?;
+
+    exports.function("creates_dates", creates_dates)?
+        .function("constructs_instances", constructs_instances)?
+        .function("checks_instances", checks_instances)?
+        .function("compares_values", compares_values)?
+        .function("gets_prototypes", gets_prototypes)?;
+
+    exports.function("round_trips_orders", round_trips_orders)?
+        .function("totals_orders", totals_orders)?
+        .function("counts_words", counts_words)?
+        .function("round_trips_numeric_keys", round_trips_numeric_keys)?
+        .function("round_trips_string_maps", round_trips_string_maps)?
+        .function("sums_numbers", sums_numbers)?
+        .function("round_trips_json", round_trips_json)?
+        .function("stringifies_json", stringifies_json)?;
+
+    exports.function("adds_days", adds_days)?
+        .function("returns_epoch", returns_epoch)?
+        .function("returns_just_before_epoch", returns_just_before_epoch)?;
+
+    exports.function("describes_values", describes_values)?
+        .function("sums_arrays", sums_arrays)?
+        .function("reads_typed_arrays", reads_typed_arrays)?
+        .function("creates_buffers", creates_buffers)?
+        .function("creates_externals", creates_externals)?
+        .function("reads_externals", reads_externals)?
+        .function("creates_js_promises", creates_js_promises)?;
+
+    exports.function("creates_closures", creates_closures)?
+        .function("counts_dropped_closures", counts_dropped_closures)?
+        .function("creates_counters", creates_counters)?
+        .function("creates_tagged_functions", creates_tagged_functions)?;
 
-// fn create_and_attach_event_loop(env: NapiEnv, module: NapiValue) {
-//    let core = Box::new(Core::new().unwrap());
-//    let core_js = node_api::create_external(env, core).unwrap();
-//}
+    let mut calls = 0;
+    exports.function_mut("counts_own_calls", move |_, _, ()| -> u64 {
+            calls += 1;
+            calls
+        })?
+        .function_mut("calls_back_into_itself",
+                      |_, _, (callback,): (JsFunction,)| -> Result<JsValue> {
+                          callback.call((), ())
+                      })?;
 
-fn register_test<F, A, R>(env: NapiEnv, name: &str, exports: NapiValue, f: F)
-    where F: Fn(NapiEnv, NapiValue, A) -> R,
-          A: FromNapiValues,
-          R: IntoNapiValue
-{
-    let test = create_function(env, name, f).unwrap();
-    set_named_property(env, exports, name, test).unwrap();
+    exports.function("calls_functions", calls_functions)?
+        .function("calls_functions_with_this", calls_functions_with_this)?
+        .function("calls_methods", calls_methods)?
+        .function("calls_predicates", calls_predicates)?;
+
+    exports.function("invokes_callbacks", invokes_callbacks)?
+        .function("stores_callbacks", stores_callbacks)?
+        .function("invokes_stored_callbacks", invokes_stored_callbacks)?
+        .function("invokes_callbacks_from_threads", invokes_callbacks_from_threads)?;
+
+    exports.function("creates_properties", creates_properties)?;
+    define_test_properties(exports)?;
+
+    exports.function("defines_classes", defines_classes)?
+        .function("wraps_objects", wraps_objects)?
+        .class::<Counter>()?
+        .value("VERSION", "1.0.0")?;
+    Ok(())
+});
+
+// converts arguments like JS would
+fn receives_coerced_values(_: NapiEnv,
+                           _: NapiValue,
+                           (number, string, boolean): (Coerced<f64>, Coerced<String>, Coerced<bool>))
+                           -> Vec<String> {
+    vec![number.0.to_string(), string.0, boolean.0.to_string()]
 }
 
 // returns objects
@@ -164,6 +264,688 @@ fn receives_arrays(_: NapiEnv, _: NapiValue, arg: Vec<String>) -> Vec<String> {
     arg
 }
 
-fn returns_promises(_: NapiEnv, _: NapiValue, _arg: ()) -> futures::BoxFuture<(), ()> {
+fn joins_rest_arguments(_: NapiEnv,
+                        _: NapiValue,
+                        (separator, Rest(parts)): (String, Rest<String>))
+                        -> String {
+    parts.join(&separator)
+}
+
+fn sums_rest_arguments(_: NapiEnv, _: NapiValue, Rest(numbers): Rest<f64>) -> f64 {
+    numbers.iter().sum()
+}
+
+fn counts_arguments(_: NapiEnv, _: NapiValue, args: Arguments) -> u64 {
+    args.len() as u64
+}
+
+// returns an object, which `new` returns in place of `this`
+fn describes_calls(env: NapiEnv, _: NapiValue, context: CallContext) -> Result<JsObject> {
+    let (name, Rest(_)): (String, Rest<JsValue>) = context.args_as()?;
+    let this: JsValue = context.this()?;
+    let description = JsObject::new(env)?;
+    description.set_property("name", name.as_str())?;
+    description.set_property("construct", context.is_construct_call())?;
+    description.set_property("argc", context.argc() as u64)?;
+    description.set_property("this", this)?;
+    Ok(description)
+}
+
+// `new.target` when called with `new`, `undefined` otherwise
+fn returns_new_target(env: NapiEnv, _: NapiValue, args: Arguments) -> Result<JsValue> {
+    match args.new_target() {
+        Some(new_target) => Ok(new_target),
+        None => JsUndefined::new(env).map(JsValue::from),
+    }
+}
+
+fn returns_promises(_: NapiEnv, _: NapiValue, _arg: ()) -> BoxFuture<'static, StdResult<(), ()>> {
     future::ok(()).boxed()
 }
+
+fn resolves_promises(env: NapiEnv, _: NapiValue, arg: String) -> Promise {
+    let (deferred, promise) = Deferred::new(env).unwrap();
+    deferred.resolve(arg).unwrap();
+    promise
+}
+
+fn rejects_promises(env: NapiEnv, _: NapiValue, arg: String) -> Promise {
+    let (deferred, promise) = Deferred::new(env).unwrap();
+    deferred
+        .reject(NapiError {
+                    error_message: arg,
+                    engine_error_code: 0,
+                    error_code: NapiErrorType::GenericFailure,
+                })
+        .unwrap();
+    promise
+}
+
+// buffers cannot be property names, so the error fails to convert
+fn rejects_promises_with_unconvertible_errors(env: NapiEnv,
+                                              _: NapiValue,
+                                              _: ())
+                                              -> Result<Promise> {
+    let (deferred, promise) = Deferred::new(env)?;
+    let mut err = BTreeMap::new();
+    err.insert(vec![1u8], 1u64);
+    deferred.reject(Serde(err))?;
+    Ok(promise)
+}
+
+fn resolves_promises_from_threads(env: NapiEnv, _: NapiValue, arg: u64) -> Promise {
+    let (deferred, promise) = Deferred::new(env).unwrap();
+    let deferred = deferred.into_threadsafe().unwrap();
+    std::thread::spawn(move || { deferred.resolve(arg * 2).unwrap(); });
+    promise
+}
+
+fn returns_pending_promises(_: NapiEnv,
+                            _: NapiValue,
+                            arg: u64)
+                            -> BoxFuture<'static, StdResult<u64, ()>> {
+    let (tx, rx) = oneshot::channel();
+    std::thread::spawn(move || {
+                           std::thread::sleep(std::time::Duration::from_millis(arg));
+                           tx.send(arg).unwrap();
+                       });
+    rx.map_err(|_| ()).boxed()
+}
+
+fn returns_unboxed_futures(_: NapiEnv,
+                           _: NapiValue,
+                           arg: u64)
+                           -> impl Future<Output = StdResult<u64, ()>> {
+    let (tx, rx) = oneshot::channel();
+    std::thread::spawn(move || { tx.send(arg * 2).unwrap(); });
+    rx.map_err(|_| ())
+}
+
+fn returns_legacy_promises(_: NapiEnv, _: NapiValue, arg: u64) -> futures01::BoxFuture<u64, ()> {
+    use futures01::Future;
+    futures01::future::ok(arg).boxed()
+}
+
+async fn returns_async_values(_: NapiEnv, _: NapiValue, arg: u64) -> StdResult<u64, NapiError> {
+    let (tx, rx) = oneshot::channel();
+    std::thread::spawn(move || { tx.send(arg * 2).unwrap(); });
+    rx.await.map_err(|_| NapiError {
+                         error_message: "sender dropped".to_string(),
+                         engine_error_code: 0,
+                         error_code: NapiErrorType::GenericFailure,
+                     })
+}
+
+struct Fibonacci(u64);
+
+impl AsyncTask for Fibonacci {
+    type Output = u64;
+    type JsValue = u64;
+
+    fn compute(self) -> Result<u64> {
+        if self.0 > 90 {
+            return Err(NapiError {
+                           error_message: "input too large".to_string(),
+                           engine_error_code: 0,
+                           error_code: NapiErrorType::InvalidArg,
+                       });
+        }
+        let (mut a, mut b) = (0u64, 1u64);
+        for _ in 0..self.0 {
+            let next = a + b;
+            a = b;
+            b = next;
+        }
+        Ok(a)
+    }
+
+    fn resolve(_: NapiEnv, output: u64) -> Result<u64> {
+        Ok(output)
+    }
+}
+
+fn computes_async_tasks(_: NapiEnv, _: NapiValue, arg: u64) -> Task<Fibonacci> {
+    Task(Fibonacci(arg))
+}
+
+struct Sleep(u64);
+
+impl AsyncTask for Sleep {
+    type Output = ();
+    type JsValue = ();
+
+    fn compute(self) -> Result<()> {
+        std::thread::sleep(std::time::Duration::from_millis(self.0));
+        Ok(())
+    }
+
+    fn resolve(_: NapiEnv, _: ()) -> Result<()> {
+        Ok(())
+    }
+}
+
+// saturates the threadpool so that the last task is still queued when it is
+// cancelled
+fn cancels_async_tasks(env: NapiEnv, _: NapiValue, _: ()) -> Promise {
+    for _ in 0..16 {
+        queue_async_task(env, Sleep(20)).unwrap();
+    }
+    let (promise, work) = queue_async_task(env, Sleep(0)).unwrap();
+    work.cancel().unwrap();
+    promise
+}
+
+struct ThreadsafeCallbackArgs {
+    pub callback: NapiValue,
+    pub count: u64,
+}
+
+impl FromNapiValues for ThreadsafeCallbackArgs {
+    fn from_napi_values(env: NapiEnv,
+                        this: NapiValue,
+                        napi_values: &[NapiValue])
+                        -> Result<ThreadsafeCallbackArgs> {
+        match napi_values.len() {
+            2 => {
+                Ok(ThreadsafeCallbackArgs {
+                       callback: napi_values[0],
+                       count: FromNapiValues::from_napi_values(env, this, &napi_values[1..])?,
+                   })
+            }
+            n => {
+                Err(NapiError {
+                        error_message: "expected two arguments, got ".to_string() + &n.to_string(),
+                        engine_error_code: 0,
+                        error_code: NapiErrorType::InvalidArg,
+                    })
+            }
+        }
+    }
+}
+
+fn calls_threadsafe_functions(env: NapiEnv,
+                              _: NapiValue,
+                              args: ThreadsafeCallbackArgs)
+                              -> Result<()> {
+    let callback = ThreadsafeFunction::new(env, args.callback, 2)?;
+    for thread in 0..args.count {
+        let callback = callback.try_clone()?;
+        std::thread::spawn(move || { callback.call(thread, CallMode::Blocking).unwrap(); });
+    }
+    Ok(())
+}
+
+fn aborts_async_tasks(_: NapiEnv,
+                      _: NapiValue,
+                      (millis, signal): (u64, Option<AbortSignal>))
+                      -> AbortableTask<Sleep> {
+    AbortableTask(Sleep(millis), signal)
+}
+
+fn aborts_async_functions(_: NapiEnv,
+                          _: NapiValue,
+                          (millis, signal): (u64, Option<AbortSignal>))
+                          -> Abortable<BoxFuture<'static, StdResult<u64, ()>>> {
+    let (tx, rx) = oneshot::channel();
+    std::thread::spawn(move || {
+                           std::thread::sleep(std::time::Duration::from_millis(millis));
+                           let _ = tx.send(millis);
+                       });
+    abortable(rx.map_err(|_| ()).boxed(), signal.as_ref())
+}
+
+// counts calls separately for each env the module has been loaded into
+#[derive(Default)]
+struct CallCount(Cell<u64>);
+
+fn counts_calls_per_env(env: NapiEnv, _: NapiValue, _: ()) -> Result<u64> {
+    let count = env.instance_data::<CallCount>()?;
+    count.0.set(count.0.get() + 1);
+    Ok(count.0.get())
+}
+
+// counts the cleanup hooks that have run in any env
+static CLEANUP_HOOKS_RUN: AtomicUsize = AtomicUsize::new(0);
+
+fn registers_cleanup_hooks(env: NapiEnv, _: NapiValue, (remove,): (bool,)) -> Result<()> {
+    let hook = env.add_cleanup_hook(|| {
+                                        CLEANUP_HOOKS_RUN.fetch_add(1, Ordering::SeqCst);
+                                    })?;
+    if remove {
+        hook.remove()?;
+    }
+    Ok(())
+}
+
+fn counts_cleanup_hooks(_: NapiEnv, _: NapiValue, _: ()) -> u64 {
+    CLEANUP_HOOKS_RUN.load(Ordering::SeqCst) as u64
+}
+
+#[derive(Default)]
+struct StoredReference(RefCell<Option<Reference>>);
+
+fn stores_references(env: NapiEnv, _: NapiValue, (value,): (JsValue,)) -> Result<()> {
+    let reference = Reference::new(env, value.raw(), 1)?;
+    *env.instance_data::<StoredReference>()?.0.borrow_mut() = Some(reference);
+    Ok(())
+}
+
+fn wraps_values(env: NapiEnv, _: NapiValue, (object, value): (JsObject, u64)) -> Result<()> {
+    wrap(env, object.raw(), Box::new(value))
+}
+
+fn reads_wrapped_values(env: NapiEnv, _: NapiValue, (object,): (JsObject,)) -> Result<u64> {
+    unwrap::<u64>(env, object.raw()).map(|value| unsafe { *value })
+}
+
+fn removes_wrapped_values(env: NapiEnv, _: NapiValue, (object,): (JsObject,)) -> Result<u64> {
+    remove_wrap::<u64>(env, object.raw()).map(|value| *value)
+}
+
+fn loads_references(env: NapiEnv, _: NapiValue, _: ()) -> Result<JsValue> {
+    let stored = env.instance_data::<StoredReference>()?;
+    let value = match *stored.0.borrow() {
+        Some(ref reference) => reference.value()?,
+        None => None,
+    };
+    match value {
+        Some(value) => Ok(JsValue::new(env, value)),
+        None => Ok(JsValue::new(env, get_null(env)?)),
+    }
+}
+
+fn creates_symbols(env: NapiEnv, _: NapiValue, (description,): (Option<String>,)) -> Result<JsValue> {
+    JsValue::symbol(env, description.as_ref().map(|d| d.as_str()))
+}
+
+fn describes_symbols(_: NapiEnv, _: NapiValue, (symbol,): (JsValue,)) -> Result<String> {
+    Ok(symbol.symbol_description()?.unwrap_or_default())
+}
+
+fn has_properties(_: NapiEnv, _: NapiValue, (object, key): (JsValue, JsValue)) -> Result<bool> {
+    object.has_property(key)
+}
+
+fn deletes_properties(_: NapiEnv, _: NapiValue, (object, key): (JsValue, JsValue)) -> Result<bool> {
+    object.delete_property(key)
+}
+
+// returns an iterable over 0..n
+fn creates_iterables(env: NapiEnv, _: NapiValue, (n,): (u64,)) -> Result<JsValue> {
+    let iterable = JsValue::new(env, create_object(env)?);
+    let iterator = create_function(env, "iterator", move |env, _, ()| -> Result<JsValue> {
+        let iterator = JsValue::new(env, create_object(env)?);
+        let current = Cell::new(0);
+        let next = create_function(env, "next", move |env, _, ()| -> Result<JsValue> {
+            let result = JsValue::new(env, create_object(env)?);
+            let value = current.get();
+            if value < n {
+                current.set(value + 1);
+                result.set_property("value", value)?;
+            }
+            result.set_property("done", value >= n)?;
+            Ok(result)
+        })?;
+        iterator.set_property("next", JsValue::new(env, next))?;
+        Ok(iterator)
+    })?;
+    iterable.set_property(JsValue::well_known_symbol(env, WellKnownSymbol::Iterator)?,
+                          JsValue::new(env, iterator))?;
+    iterable.set_property(JsValue::well_known_symbol(env, WellKnownSymbol::ToStringTag)?,
+                          "Range")?;
+    Ok(iterable)
+}
+
+fn creates_dates(env: NapiEnv, _: NapiValue, (time,): (f64,)) -> Result<JsValue> {
+    JsValue::global(env)?.get_property("Date")?.construct((time,))
+}
+
+#[derive(Serialize, Deserialize)]
+struct Order {
+    id: u32,
+    items: Vec<Item>,
+    note: Option<String>,
+    status: Status,
+    #[serde(with = "bytes")]
+    signature: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Item(String, f64);
+
+#[derive(Serialize, Deserialize)]
+enum Status {
+    Pending,
+    Shipped { tracking: String },
+    Cancelled(String),
+}
+
+// serializes bytes as a `Buffer` instead of an array of numbers
+mod bytes {
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &Vec<u8>, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(bytes)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        struct BytesVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for BytesVisitor {
+            type Value = Vec<u8>;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a buffer")
+            }
+
+            fn visit_byte_buf<E>(self, bytes: Vec<u8>) -> Result<Vec<u8>, E> {
+                Ok(bytes)
+            }
+        }
+
+        deserializer.deserialize_byte_buf(BytesVisitor)
+    }
+}
+
+fn round_trips_orders(_: NapiEnv, _: NapiValue, order: Serde<Order>) -> Serde<Order> {
+    order
+}
+
+fn totals_orders(env: NapiEnv, _: NapiValue, (order,): (JsValue,)) -> Result<f64> {
+    let order: Order = from_js(env, order.raw())?;
+    Ok(order.items.iter().map(|item| item.1).sum())
+}
+
+fn counts_words(env: NapiEnv, _: NapiValue, text: String) -> Result<JsValue> {
+    let mut counts = BTreeMap::new();
+    for word in text.split_whitespace() {
+        *counts.entry(word).or_insert(0) += 1;
+    }
+    to_js(env, &counts).map(|value| JsValue::new(env, value))
+}
+
+fn round_trips_numeric_keys(_: NapiEnv,
+                            _: NapiValue,
+                            map: Serde<BTreeMap<u32, String>>)
+                            -> Serde<BTreeMap<u32, String>> {
+    map
+}
+
+fn round_trips_string_maps(_: NapiEnv,
+                           _: NapiValue,
+                           map: Serde<BTreeMap<String, String>>)
+                           -> Serde<BTreeMap<String, String>> {
+    map
+}
+
+fn sums_numbers(_: NapiEnv, _: NapiValue, numbers: Serde<Vec<f64>>) -> f64 {
+    numbers.0.iter().sum()
+}
+
+fn round_trips_json(_: NapiEnv, _: NapiValue, value: serde_json::Value) -> serde_json::Value {
+    value
+}
+
+fn stringifies_json(_: NapiEnv, _: NapiValue, value: serde_json::Value) -> String {
+    value.to_string()
+}
+
+fn adds_days(_: NapiEnv, _: NapiValue, (time, days): (SystemTime, i64)) -> SystemTime {
+    let duration = Duration::from_secs(days.abs() as u64 * 24 * 60 * 60);
+    if days < 0 { time - duration } else { time + duration }
+}
+
+fn returns_epoch(_: NapiEnv, _: NapiValue, _: ()) -> SystemTime {
+    UNIX_EPOCH
+}
+
+// half a millisecond before the epoch, which rounds down to -1
+fn returns_just_before_epoch(_: NapiEnv, _: NapiValue, _: ()) -> SystemTime {
+    UNIX_EPOCH - Duration::from_micros(500)
+}
+
+fn constructs_instances(_: NapiEnv,
+                        _: NapiValue,
+                        (constructor, arg): (JsValue, JsValue))
+                        -> Result<JsValue> {
+    constructor.construct((arg,))
+}
+
+fn checks_instances(_: NapiEnv, _: NapiValue, (value, constructor): (JsValue, JsValue)) -> Result<bool> {
+    value.instance_of(&constructor)
+}
+
+fn compares_values(_: NapiEnv, _: NapiValue, (a, b): (JsValue, JsValue)) -> Result<bool> {
+    a.strict_equals(&b)
+}
+
+fn gets_prototypes(_: NapiEnv, _: NapiValue, (value,): (JsValue,)) -> Result<JsValue> {
+    value.get_prototype()
+}
+
+// downcasts to the most specific wrapper type
+fn describes_values(_: NapiEnv, _: NapiValue, (value,): (JsValue,)) -> Result<String> {
+    if value.is::<JsArray>()? {
+        Ok(format!("array of {}", value.downcast::<JsArray>()?.len()?))
+    } else if value.is::<JsBuffer>()? {
+        Ok(format!("buffer {:?}", value.downcast::<JsBuffer>()?.data()?))
+    } else if value.is::<JsTypedArray>()? {
+        let array = value.downcast::<JsTypedArray>()?;
+        Ok(format!("{:?} array of {}", array.array_type()?, array.len()?))
+    } else if value.is::<JsDate>()? {
+        Ok(format!("date {}", value.downcast::<JsDate>()?.time()?))
+    } else if value.is::<JsError>()? {
+        Ok(format!("error {}", value.downcast::<JsError>()?.message()?))
+    } else if value.is::<JsPromise>()? {
+        Ok("promise".to_string())
+    } else if value.is::<JsFunction>()? {
+        Ok("function".to_string())
+    } else if value.is::<JsObject>()? {
+        Ok(format!("object with {} keys", value.downcast::<JsObject>()?.property_names()?.len()?))
+    } else if value.is::<JsString>()? {
+        Ok(format!("string {}", value.downcast::<JsString>()?.value()?))
+    } else if value.is::<JsNumber>()? {
+        Ok(format!("number {}", value.downcast::<JsNumber>()?.value()?))
+    } else {
+        Ok(format!("{:?}", value.type_of()?))
+    }
+}
+
+fn sums_arrays(_: NapiEnv, _: NapiValue, (array,): (JsArray,)) -> Result<f64> {
+    let mut sum = 0.0;
+    for i in 0..array.len()? {
+        sum += array.get(i)?.downcast::<JsNumber>()?.value()?;
+    }
+    Ok(sum)
+}
+
+fn reads_typed_arrays(_: NapiEnv, _: NapiValue, (array,): (JsTypedArray,)) -> Result<Vec<u8>> {
+    Ok(array.data()?.to_vec())
+}
+
+fn creates_buffers(env: NapiEnv, _: NapiValue, text: String) -> Result<JsBuffer> {
+    JsBuffer::new(env, text.as_bytes())
+}
+
+fn creates_externals(env: NapiEnv, _: NapiValue, text: String) -> Result<JsExternal> {
+    JsExternal::new(env, text)
+}
+
+// only externals made by `creates_externals` are passed in
+fn reads_externals(_: NapiEnv, _: NapiValue, (external,): (JsExternal,)) -> Result<String> {
+    unsafe { external.value::<String>() }.map(|text| text.clone())
+}
+
+fn creates_js_promises(env: NapiEnv, _: NapiValue, text: String) -> Result<Promise> {
+    let (deferred, promise) = JsPromise::new(env)?;
+    deferred.resolve(text)?;
+    Ok(promise.into())
+}
+
+// counts the closures of created functions that have been dropped
+static DROPPED_CLOSURES: AtomicUsize = AtomicUsize::new(0);
+
+struct ClosureGuard;
+
+impl Drop for ClosureGuard {
+    fn drop(&mut self) {
+        DROPPED_CLOSURES.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+fn creates_closures(env: NapiEnv, _: NapiValue, value: u64) -> Result<JsFunction> {
+    let guard = ClosureGuard;
+    JsFunction::new(env, "closure", move |_, _, ()| {
+        let _ = &guard;
+        value
+    })
+}
+
+fn counts_dropped_closures(_: NapiEnv, _: NapiValue, _: ()) -> u64 {
+    DROPPED_CLOSURES.load(Ordering::SeqCst) as u64
+}
+
+// each counter counts its own calls
+fn creates_counters(env: NapiEnv, _: NapiValue, start: u64) -> Result<JsFunction> {
+    let mut count = start;
+    JsFunction::new_mut(env, "counter", move |_, _, ()| {
+        count += 1;
+        count
+    })
+}
+
+fn creates_tagged_functions(env: NapiEnv, _: NapiValue, tag: String) -> Result<JsFunction> {
+    JsFunction::with_data(env, "tagged", tag, |_, _, context: CallContext| -> Result<String> {
+        let tag = context.function_data::<String>().expect("function has no tag");
+        let (value,): (String,) = context.args_as()?;
+        Ok(format!("{}: {}", tag, value))
+    })
+}
+
+fn calls_functions(_: NapiEnv, _: NapiValue, (f, a, b): (JsFunction, f64, String)) -> Result<String> {
+    f.call((), (a, b))
+}
+
+fn calls_functions_with_this(_: NapiEnv,
+                             _: NapiValue,
+                             (f, this): (JsFunction, JsValue))
+                             -> Result<JsValue> {
+    f.call(this, ())
+}
+
+fn calls_methods(_: NapiEnv, _: NapiValue, (object, name): (JsObject, String)) -> Result<u64> {
+    object.call_method(&name, (2u64,))
+}
+
+// the result type is only named through the turbofish
+fn calls_predicates(_: NapiEnv,
+                    _: NapiValue,
+                    (predicate, object): (JsFunction, JsObject))
+                    -> Result<String> {
+    if predicate.call::<bool>((), (1u64, 2u64))? {
+        object.call_method::<String>("describe", ())
+    } else {
+        Ok("rejected".to_string())
+    }
+}
+
+fn invokes_callbacks(_: NapiEnv,
+                     _: NapiValue,
+                     (callback,): (JsCallback<(String, u32), bool>,))
+                     -> Result<bool> {
+    callback.call(("answer".to_string(), 42))
+}
+
+#[derive(Default)]
+struct StoredCallback(RefCell<Option<PersistentCallback<(u64,), u64>>>);
+
+fn stores_callbacks(env: NapiEnv, _: NapiValue, (callback,): (JsCallback<(u64,), u64>,)) -> Result<()> {
+    *env.instance_data::<StoredCallback>()?.0.borrow_mut() = Some(callback.persist()?);
+    Ok(())
+}
+
+fn invokes_stored_callbacks(env: NapiEnv, _: NapiValue, (value,): (u64,)) -> Result<u64> {
+    let stored = env.instance_data::<StoredCallback>()?;
+    let stored = stored.0.borrow();
+    match *stored {
+        Some(ref callback) => callback.call((value,)),
+        None => Err(NapiError {
+                        error_message: "no callback stored".to_string(),
+                        engine_error_code: 0,
+                        error_code: NapiErrorType::GenericFailure,
+                    }),
+    }
+}
+
+fn invokes_callbacks_from_threads(_: NapiEnv,
+                                  _: NapiValue,
+                                  (callback, count): (JsCallback<(u64, String), ()>, u64))
+                                  -> Result<()> {
+    let callback = callback.into_threadsafe(0)?;
+    for i in 0..count {
+        let callback = callback.clone();
+        std::thread::spawn(move || {
+            callback
+                .call((i, format!("thread {}", i)), CallMode::Blocking)
+                .expect("error calling callback");
+        });
+    }
+    Ok(())
+}
+
+fn define_test_properties(exports: &mut Exports) -> Result<()> {
+    let properties = creates_properties(exports.env(), exports.object(), ())?;
+    set_named_property(exports.env(), exports.object(), "properties", properties.raw())?;
+    Ok(())
+}
+
+// a new object with values, an accessor and a method
+fn creates_properties(env: NapiEnv, _: NapiValue, _: ()) -> Result<JsValue> {
+    let stored = Rc::new(Cell::new(0u64));
+    let getter_stored = stored.clone();
+    let properties = create_object(env)?;
+    define_properties(env,
+                      properties,
+                      vec![PropertyDescriptor::value("VERSION", "1.0.0").enumerable(),
+                           PropertyDescriptor::value("counter", 0u64).writable(),
+                           PropertyDescriptor::getter("stored", move |_, _| getter_stored.get())
+                               .setter(move |_, _, value: u64| stored.set(value))
+                               .enumerable(),
+                           PropertyDescriptor::method("add", |_, _, (a, b): (u64, u64)| a + b)])?;
+    Ok(JsValue::new(env, properties))
+}
+
+// a new constructor for `Counter`, which is not kept alive by the exports
+fn defines_classes(env: NapiEnv, _: NapiValue, _: ()) -> Result<JsValue> {
+    define_class::<Counter>(env).map(|constructor| JsValue::new(env, constructor))
+}
+
+// an object wrapping a value smaller than any class instance
+fn wraps_objects(env: NapiEnv, _: NapiValue, _: ()) -> Result<JsValue> {
+    let object = create_object(env)?;
+    wrap(env, object, Box::new(1u8))?;
+    Ok(JsValue::new(env, object))
+}
+
+// a class exported through `Exports::class`
+struct Counter {
+    count: u64,
+}
+
+impl Class for Counter {
+    const NAME: &'static str = "Counter";
+    type Args = (Option<u64>,);
+
+    fn constructor(_: NapiEnv, _: NapiValue, (start,): (Option<u64>,)) -> Result<Self> {
+        Ok(Counter { count: start.unwrap_or(0) })
+    }
+
+    fn properties() -> Vec<PropertyDescriptor> {
+        vec![PropertyDescriptor::instance_method("increment", |counter: &mut Counter, _, ()| {
+                 counter.count += 1;
+                 counter.count
+             }),
+             PropertyDescriptor::instance_getter("value", |counter: &mut Counter, _| counter.count)
+                 .instance_setter(|counter: &mut Counter, _, value: u64| counter.count = value)]
+    }
+}